@@ -4,9 +4,17 @@ use bevy::prelude::*;
 #[derive(Debug, Component)]
 pub struct LinesContainer;
 
+// the full height of the list inside LinesContainer, the visible rows are its children
+#[derive(Debug, Component)]
+pub struct LinesContent;
+
 #[derive(Debug, Component)]
 pub struct SettingContainer;
 
+// the search box of the filter bar
+#[derive(Debug, Component)]
+pub struct SearchInput;
+
 #[derive(Debug, Component)]
 pub struct ProgressBar;
 
@@ -16,8 +24,9 @@ pub struct IndexOfline(pub usize);
 #[derive(Debug, Component)]
 pub struct TaskButton;
 
+// the preset started by the task button
 #[derive(Debug, Component)]
-pub struct TaskButtonType(pub bool);
+pub struct TaskButtonType(pub String);
 
 // Define a new trait that combines MenuButtonType and MenuButtonNext
 pub trait MenuButtonTrait: MenuButtonType + MenuButtonNext + std::fmt::Debug {}
@@ -32,18 +41,174 @@ pub struct MenuButton {
 #[derive(Debug, Component)]
 pub struct FileLineBar;
 
+// the index cell of a row, click to select the line
+#[derive(Debug, Component)]
+pub struct SelectButton;
+
+// the number of selected lines in the selection bar
+#[derive(Debug, Component)]
+pub struct SelectionText;
+
 #[derive(Debug, Component)]
 pub struct ReplaceButton;
 
+#[derive(Debug, Component)]
+pub struct UndoReplaceButton;
+
 #[derive(Debug, Component)]
 pub struct SnapshotButton(pub bool);
 
 #[derive(Debug, Component)]
 pub struct OpenButton;
 
+#[derive(Debug, Component)]
+pub struct RevealButton;
+
+#[derive(Debug, Component)]
+pub struct CopyPathButton;
+
 #[derive(Debug, Component)]
 pub struct PreviewWindow;
 
+#[derive(Debug, Component)]
+pub struct CompareButton;
+
+#[derive(Debug, Component)]
+pub struct CompareWindow;
+
+// the composed frames, drag to move the split, right-drag to pan
+#[derive(Debug, Component)]
+pub struct CompareImage;
+
+#[derive(Debug, Component)]
+pub struct CompareText;
+
+#[derive(Debug, Clone, Copy)]
+pub enum CompareAction {
+    PrevFrame,
+    NextFrame,
+    Back,
+    Forward,
+    Mode,
+    ZoomIn,
+    ZoomOut,
+    Close,
+}
+
+#[derive(Debug, Component)]
+pub struct CompareControl(pub CompareAction);
+
+#[derive(Debug, Component)]
+pub struct EditButton;
+
+#[derive(Debug, Component)]
+pub struct EditWindow;
+
+// the frame at the scrub position, drag on it to draw the crop
+#[derive(Debug, Component)]
+pub struct EditImage;
+
+// click or drag to scrub
+#[derive(Debug, Component)]
+pub struct EditTimeline;
+
+#[derive(Debug, Component)]
+pub struct EditText;
+
+// the nodes drawn over the frame and the timeline
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Component)]
+pub enum EditOverlay {
+    CropBox,
+    Range, // from the in to the out point
+    Cursor,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum EditAction {
+    Back,
+    Forward,
+    SetIn,
+    SetOut,
+    ClearTrim,
+    ClearCrop,
+    Segment,
+    Close,
+}
+
+#[derive(Debug, Component)]
+pub struct EditControl(pub EditAction);
+
+#[derive(Debug, Component)]
+pub struct LogButton;
+
+#[derive(Debug, Component)]
+pub struct LogWindow;
+
+#[derive(Debug, Component)]
+pub struct LogText;
+
+#[derive(Debug, Component)]
+pub struct ErrorText;
+
+#[derive(Debug, Component)]
+pub struct QualityText;
+
+#[derive(Debug, Component)]
+pub struct StatsText;
+
+#[derive(Debug, Component)]
+pub struct MediaText;
+
+#[derive(Debug, Component)]
+pub struct DetailsButton;
+
+#[derive(Debug, Component)]
+pub struct DetailsWindow;
+
+#[derive(Debug, Component)]
+pub struct DetailsText;
+
+#[derive(Debug, Component)]
+pub struct RuleToggleButton(pub usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgFieldKind {
+    Key,
+    Value,
+}
+
+// an editable box of an existing argument
+#[derive(Debug, Component)]
+pub struct ArgField {
+    pub group: ArgGroup,
+    pub row: usize,
+    pub kind: ArgFieldKind,
+}
+
+// the boxes of a new argument (at the bottom of the panel)
+#[derive(Debug, Component)]
+pub struct ArgInput {
+    pub group: ArgGroup,
+    pub kind: ArgFieldKind,
+}
+
+#[derive(Debug, Component)]
+pub struct ArgSubmitButton(pub ArgGroup);
+
+#[derive(Debug, Clone, Copy)]
+pub enum ArgRowAction {
+    Up,
+    Down,
+    Remove,
+}
+
+#[derive(Debug, Component)]
+pub struct ArgRowButton {
+    pub group: ArgGroup,
+    pub row: usize,
+    pub action: ArgRowAction,
+}
+
 #[derive(Debug, Component)]
 pub struct ToastMaker(pub Timer);
 
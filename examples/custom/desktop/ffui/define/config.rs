@@ -0,0 +1,382 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use super::ffmpeg_arg::Preset;
+
+// saved next to files_state.json
+pub const CONFIG_FILE: &str = "config.json";
+
+// the sidecar conventions ffui can update after a replace
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SidecarKind {
+    Eagle, // `metadata.json` in the `<name>.info` folder of an Eagle library
+    Nfo,   // `<stem>.nfo` of Kodi / Jellyfin
+}
+
+// the files under `root` share the same sidecar conventions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibraryConfig {
+    pub root: String, // path prefix, empty matches every file
+    #[serde(default)]
+    pub sidecars: Vec<SidecarKind>,
+}
+
+// where the encoded files are written
+// placeholders: {dir} directory of the source, {stem} file name of the source without
+// extension, {preset} name of the preset, {ext} extension of the preset, {cwd} working directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutputConfig {
+    pub template: String, // e.g. "{dir}/{stem}.{preset}.{ext}" or "/mnt/encoded/{stem}.{ext}"
+    #[serde(default)]
+    pub staging: bool, // encode into a temp folder and move the file when it is done
+}
+
+impl Default for OutputConfig {
+    fn default() -> Self {
+        Self {
+            template: "{cwd}/{stem}.{ext}".to_string(),
+            staging: false,
+        }
+    }
+}
+
+impl OutputConfig {
+    pub fn render(&self, path: &str, preset: &Preset) -> Option<PathBuf> {
+        let source = Path::new(path);
+        let dir = source.parent()?.to_str()?;
+        let stem = source.file_stem()?.to_str()?;
+        let cwd = std::env::current_dir().ok()?;
+        // a preset name may contain a path separator
+        let preset_name = preset.name.replace(['/', '\\'], "_");
+        let output = self
+            .template
+            .replace("{dir}", dir)
+            .replace("{stem}", stem)
+            .replace("{preset}", &preset_name)
+            .replace("{ext}", &preset.ext)
+            .replace("{cwd}", cwd.to_str()?);
+        let output = PathBuf::from(output);
+        // relative templates are relative to the working directory
+        Some(if output.is_absolute() {
+            output
+        } else {
+            cwd.join(output)
+        })
+    }
+
+    // the file ffmpeg writes while the task is running
+    // the pid keeps two instances (e.g. ffui and a headless run) staging the same index apart
+    pub fn staging_path(&self, index: usize, output: &Path) -> Option<PathBuf> {
+        if !self.staging {
+            return None;
+        }
+        let name = output.file_name()?.to_str()?;
+        Some(std::env::temp_dir().join("ffui").join(format!(
+            "{}-{}-{}",
+            std::process::id(),
+            index,
+            name
+        )))
+    }
+}
+
+// a folder whose new files are imported once they are completely written
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchFolder {
+    pub path: String,
+    #[serde(default)]
+    pub patterns: Vec<String>, // e.g. ["*.mov", "clip_??.mp4"], empty matches every file
+    #[serde(default)]
+    pub recursive: bool,
+    #[serde(default = "default_stable_secs")]
+    pub stable_secs: u64, // the size must not change for this long
+    #[serde(default)]
+    pub auto_queue: Option<String>, // queue the new files with this preset
+}
+
+fn default_stable_secs() -> u64 {
+    10
+}
+
+impl WatchFolder {
+    pub fn new(path: &str) -> Self {
+        Self {
+            path: path.to_string(),
+            patterns: vec![],
+            recursive: false,
+            stable_secs: default_stable_secs(),
+            auto_queue: None,
+        }
+    }
+
+    // a folder and its settings on one line, separated by `|`:
+    // `/ingest | *.mov,*.mp4 | recursive | 30s | queue=h264`
+    // the settings can come in any order, the ones left out keep their defaults
+    pub fn parse(line: &str) -> Result<Self, String> {
+        let mut fields = line.split('|').map(str::trim);
+        let mut folder = Self::new(fields.next().unwrap_or_default());
+        for field in fields.filter(|field| !field.is_empty()) {
+            if field == "recursive" {
+                folder.recursive = true;
+            } else if let Some(preset) = field.strip_prefix("queue=") {
+                let preset = preset.trim();
+                if preset.is_empty() {
+                    return Err(format!("no preset after queue= in {}", line));
+                }
+                folder.auto_queue = Some(preset.to_string());
+            } else if let Some(secs) = field
+                .strip_suffix('s')
+                .filter(|secs| !secs.is_empty() && secs.bytes().all(|b| b.is_ascii_digit()))
+            {
+                folder.stable_secs = secs
+                    .parse()
+                    .map_err(|e| format!("invalid wait time {}: {}", field, e))?;
+            } else {
+                folder.patterns.extend(
+                    field
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|pattern| !pattern.is_empty())
+                        .map(str::to_string),
+                );
+            }
+        }
+        Ok(folder)
+    }
+
+    // `*` matches any characters and `?` one character of the file name
+    pub fn matches(&self, name: &str) -> bool {
+        self.patterns.is_empty()
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| glob_match(pattern.as_bytes(), name.as_bytes()))
+    }
+}
+
+fn glob_match(pattern: &[u8], name: &[u8]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some((b'*', rest)) => (0..=name.len()).any(|i| glob_match(rest, &name[i..])),
+        Some((b'?', rest)) => !name.is_empty() && glob_match(rest, &name[1..]),
+        Some((c, rest)) => name
+            .split_first()
+            .is_some_and(|(n, name)| n.eq_ignore_ascii_case(c) && glob_match(rest, name)),
+    }
+}
+
+// how the frames of a contact sheet are chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SheetMode {
+    Even,  // evenly spaced over the duration
+    Scene, // where the scene changes, filled up with evenly spaced frames
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SheetConfig {
+    pub mode: SheetMode,
+    pub frames: usize,
+    pub columns: u32,
+    pub height: u32,          // height of each frame in pixels
+    pub scene_threshold: f64, // 0..1, how much a frame differs from the previous one
+}
+
+impl Default for SheetConfig {
+    fn default() -> Self {
+        Self {
+            mode: SheetMode::Even,
+            frames: 12,
+            columns: 4,
+            height: 180,
+            scene_threshold: 0.3,
+        }
+    }
+}
+
+// the score comparing an encoded file with its source
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum QualityMetric {
+    Ssim, // 0..1
+    Psnr, // dB
+    Vmaf, // 0..100, needs an ffmpeg build with libvmaf
+}
+
+impl QualityMetric {
+    pub fn name(&self) -> &'static str {
+        match self {
+            QualityMetric::Ssim => "SSIM",
+            QualityMetric::Psnr => "PSNR",
+            QualityMetric::Vmaf => "VMAF",
+        }
+    }
+
+    pub fn default_threshold(&self) -> f64 {
+        match self {
+            QualityMetric::Ssim => 0.95,
+            QualityMetric::Psnr => 35.0,
+            QualityMetric::Vmaf => 90.0,
+        }
+    }
+
+    // the thresholds the menu button cycles through, in increasing order
+    pub fn threshold_steps(&self) -> &'static [f64] {
+        match self {
+            QualityMetric::Ssim => &[0.9, 0.93, 0.95, 0.97, 0.98],
+            QualityMetric::Psnr => &[30.0, 33.0, 35.0, 38.0, 40.0],
+            QualityMetric::Vmaf => &[80.0, 85.0, 90.0, 93.0, 95.0],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QualityConfig {
+    pub enabled: bool, // measure each file after its task is done
+    pub metric: QualityMetric,
+    #[serde(default)]
+    pub threshold: Option<f64>, // replace is blocked below it, None uses the default of the metric
+}
+
+impl Default for QualityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            metric: QualityMetric::Ssim,
+            threshold: None,
+        }
+    }
+}
+
+impl QualityConfig {
+    // a score of another metric (e.g. SSIM when libvmaf is missing) uses the default of its metric
+    pub fn threshold(&self, metric: QualityMetric) -> f64 {
+        match self.threshold {
+            Some(threshold) if metric == self.metric => threshold,
+            _ => metric.default_threshold(),
+        }
+    }
+
+    pub fn passes(&self, metric: QualityMetric, score: f64) -> bool {
+        score >= self.threshold(metric)
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+pub struct AppConfig {
+    #[serde(default)]
+    pub libraries: Vec<LibraryConfig>,
+    #[serde(default)]
+    pub output: OutputConfig,
+    #[serde(default)]
+    pub watch: Vec<WatchFolder>,
+    #[serde(default)]
+    pub sheet: SheetConfig,
+    #[serde(default)]
+    pub quality: QualityConfig,
+}
+
+impl Default for AppConfig {
+    fn default() -> Self {
+        Self {
+            libraries: vec![LibraryConfig {
+                root: String::new(),
+                sidecars: vec![SidecarKind::Eagle],
+            }],
+            output: OutputConfig::default(),
+            watch: vec![],
+            sheet: SheetConfig::default(),
+            quality: QualityConfig::default(),
+        }
+    }
+}
+
+impl AppConfig {
+    pub fn load() -> Self {
+        let Ok(json) = std::fs::read_to_string(CONFIG_FILE) else {
+            return Self::default();
+        };
+        serde_json::from_str(&json).unwrap_or_else(|e| {
+            warn!("Failed to deserialize {}: {}", CONFIG_FILE, e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(CONFIG_FILE, json)
+            .map_err(|e| format!("Failed to save {}: {}", CONFIG_FILE, e))
+    }
+
+    // the library with the longest root that contains the path
+    pub fn library(&self, path: &str) -> Option<&LibraryConfig> {
+        self.libraries
+            .iter()
+            .filter(|library| path.starts_with(&library.root))
+            .max_by_key(|library| library.root.len())
+    }
+
+    pub fn sidecars(&self, path: &str) -> &[SidecarKind] {
+        self.library(path)
+            .map_or(&[], |library| library.sidecars.as_slice())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn glob(pattern: &str, name: &str) -> bool {
+        glob_match(pattern.as_bytes(), name.as_bytes())
+    }
+
+    #[test]
+    fn glob_wildcards() {
+        assert!(glob("*.mov", "A001_C002.mov"));
+        assert!(glob("*.mov", ".mov"));
+        assert!(!glob("*.mov", "clip.mov.part"));
+        assert!(glob("clip_??.mp4", "clip_07.mp4"));
+        assert!(!glob("clip_??.mp4", "clip_7.mp4"));
+        assert!(glob("*_cam*_*.mxf", "day2_camB_0042.mxf"));
+        assert!(glob("*", ""));
+        assert!(!glob("", "clip.mp4"));
+    }
+
+    #[test]
+    fn glob_ignores_case() {
+        assert!(glob("*.MOV", "clip.mov"));
+        assert!(glob("Clip_??.mp4", "CLIP_01.MP4"));
+    }
+
+    #[test]
+    fn watch_folder_patterns() {
+        let mut folder = WatchFolder::new("/ingest");
+        assert!(folder.matches("anything.txt"));
+        folder.patterns = vec!["*.mov".to_string(), "*.mp4".to_string()];
+        assert!(folder.matches("clip.mp4"));
+        assert!(!folder.matches("clip.mp4.part"));
+    }
+
+    #[test]
+    fn watch_folder_settings_of_a_line() {
+        let folder = WatchFolder::parse("/ingest").unwrap();
+        assert_eq!(folder.path, "/ingest");
+        assert!(folder.patterns.is_empty());
+        assert!(!folder.recursive);
+        assert_eq!(folder.stable_secs, 10);
+        assert_eq!(folder.auto_queue, None);
+
+        let folder =
+            WatchFolder::parse(" /ingest | *.mov, *.mp4 |recursive| 30s | queue=h264 ").unwrap();
+        assert_eq!(folder.path, "/ingest");
+        assert_eq!(folder.patterns, ["*.mov", "*.mp4"]);
+        assert!(folder.recursive);
+        assert_eq!(folder.stable_secs, 30);
+        assert_eq!(folder.auto_queue.as_deref(), Some("h264"));
+
+        // a name ending with s is a pattern, not a wait time
+        let folder = WatchFolder::parse("/ingest | clips").unwrap();
+        assert_eq!(folder.patterns, ["clips"]);
+        assert_eq!(folder.stable_secs, 10);
+        assert!(WatchFolder::parse("/ingest | queue=").is_err());
+    }
+}
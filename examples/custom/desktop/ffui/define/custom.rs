@@ -1,11 +1,24 @@
 use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+
+use super::config::{QualityConfig, QualityMetric};
+use super::ffmpeg_arg::ArgKeyValue;
+use super::hwaccel::software_filter;
+use super::probe::MediaInfo;
 
 #[derive(Debug, Clone)]
 pub enum ProgressType {
     Total,
     Current,
+    Cancelled,
+    Finished,
+    Failed(String),
+    Stats(EncodeStats),
+    Media(MediaInfo),
+    Pass(u32, u32), // (pass, passes) of a two-pass encode
 }
 
 #[derive(Debug, Clone, Copy)]
@@ -14,13 +27,62 @@ pub enum ProcessSignal {
     TaskInterrupt(usize),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
 pub struct ProgressStatistics {
     pub total: u64,
     pub current: u64,
     pub percent: f64,
+    #[serde(default)]
+    pub stats: EncodeStats,
+    #[serde(default)]
+    pub eta: Option<u64>, // remaining seconds, estimated from the speed
+    #[serde(default)]
+    pub pass: Option<(u32, u32)>, // (pass, passes) of a two-pass encode
+}
+
+// live encoding statistics reported by `ffmpeg -progress`
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct EncodeStats {
+    pub fps: f64,
+    pub speed: f64,      // multiplier of realtime, e.g. 2.5x
+    pub bitrate: f64,    // kbits/s
+    pub total_size: u64, // bytes of the output written so far
+}
+
+impl EncodeStats {
+    // apply one `key=value` line of the progress output,`N/A` values are ignored
+    pub fn apply(&mut self, key: &str, value: &str) {
+        let value = value.trim();
+        match key {
+            "fps" => {
+                if let Ok(fps) = value.parse() {
+                    self.fps = fps;
+                }
+            }
+            "speed" => {
+                if let Ok(speed) = value.trim_end_matches('x').trim().parse() {
+                    self.speed = speed;
+                }
+            }
+            "bitrate" => {
+                if let Ok(bitrate) = value.trim_end_matches("kbits/s").trim().parse() {
+                    self.bitrate = bitrate;
+                }
+            }
+            "total_size" => {
+                if let Ok(size) = value.parse() {
+                    self.total_size = size;
+                }
+            }
+            _ => {}
+        }
+    }
 }
 
+// the state of a line was updated by a progress message
+#[derive(Debug, Event)]
+pub struct ProgressChanged(pub usize);
+
 // Progress statistics for each file
 // service Sender & Receiver
 #[derive(Debug, Clone)]
@@ -28,6 +90,9 @@ pub struct ProgressInfo {
     pub progress_type: ProgressType,
     pub progress_value: u64,
     pub progress_index: Option<usize>,
+    // the line the index stood for when the message was made, set by the analysis,
+    // which runs on after its lines were removed or replaced by another import
+    pub progress_path: Option<String>,
 }
 
 impl ProgressInfo {
@@ -37,6 +102,7 @@ impl ProgressInfo {
             progress_type: ProgressType::Total,
             progress_value: value,
             progress_index: Some(idx),
+            progress_path: None,
         }
     }
     // Create a new ProgressInfo instance with the Current type
@@ -45,6 +111,68 @@ impl ProgressInfo {
             progress_type: ProgressType::Current,
             progress_value: value,
             progress_index: Some(idx),
+            progress_path: None,
+        }
+    }
+    // Create a new ProgressInfo instance with the Cancelled type
+    pub fn cancelled(idx: usize) -> Self {
+        Self {
+            progress_type: ProgressType::Cancelled,
+            progress_value: 0,
+            progress_index: Some(idx),
+            progress_path: None,
+        }
+    }
+    // Create a new ProgressInfo instance with the Finished type (ffmpeg reported progress=end and exited successfully)
+    pub fn finished(idx: usize) -> Self {
+        Self {
+            progress_type: ProgressType::Finished,
+            progress_value: 0,
+            progress_index: Some(idx),
+            progress_path: None,
+        }
+    }
+    // Create a new ProgressInfo instance with the Stats type
+    pub fn stats(stats: EncodeStats, idx: usize) -> Self {
+        Self {
+            progress_type: ProgressType::Stats(stats),
+            progress_value: 0,
+            progress_index: Some(idx),
+            progress_path: None,
+        }
+    }
+    // Create a new ProgressInfo instance with the Media type (result of ffprobe)
+    pub fn media(info: MediaInfo, idx: usize) -> Self {
+        Self {
+            progress_type: ProgressType::Media(info),
+            progress_value: 0,
+            progress_index: Some(idx),
+            progress_path: None,
+        }
+    }
+    // Create a new ProgressInfo instance with the Pass type (a pass of a two-pass encode started)
+    pub fn pass(pass: u32, passes: u32, idx: usize) -> Self {
+        Self {
+            progress_type: ProgressType::Pass(pass, passes),
+            progress_value: 0,
+            progress_index: Some(idx),
+            progress_path: None,
+        }
+    }
+    // the message only applies to the line with this path
+    pub fn with_path(self, path: &str) -> Self {
+        Self {
+            progress_path: Some(path.to_string()),
+            ..self
+        }
+    }
+    // Create a new ProgressInfo instance with the Failed type
+    pub fn failed(message: String, idx: usize) -> Self {
+        Self {
+            progress_type: ProgressType::Failed(message),
+            progress_value: 0,
+            progress_index: Some(idx),
+            progress_path: None,
         }
     }
 }
@@ -54,9 +182,186 @@ impl ProgressInfo {
 pub enum TaskStatus {
     #[default]
     Waiting,
+    Queued,
     Running,
     Done,
     Replaced,
+    Failed,
+    Cancelled,
+    Skipped,     // the rules decided that the file does not need transcoding
+    Interrupted, // was running when ffui exited, resumed or restarted (see recover_lines)
+}
+
+// an image for the preview window, a grid is scrubbed cell by cell with the cursor
+#[derive(Debug)]
+pub struct PreviewImage {
+    pub image: image::RgbaImage,
+    pub columns: u32,
+    pub cells: usize, // 0 for a single frame
+}
+
+// how the comparison view shows the two frames
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum CompareMode {
+    #[default]
+    Split, // the source left of the slider, the output right of it
+    SideBySide,
+}
+
+// the quality of an encoded file compared with its source
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum QualityScore {
+    Measuring,
+    Score(QualityMetric, f64),
+    Failed(String),
+}
+
+impl QualityScore {
+    pub fn label(&self) -> String {
+        match self {
+            QualityScore::Measuring => "measuring quality...".to_string(),
+            QualityScore::Score(QualityMetric::Ssim, score) => format!("SSIM {:.4}", score),
+            QualityScore::Score(QualityMetric::Psnr, score) => format!("PSNR {:.2} dB", score),
+            QualityScore::Score(QualityMetric::Vmaf, score) => format!("VMAF {:.1}", score),
+            QualityScore::Failed(_) => "quality unknown".to_string(),
+        }
+    }
+
+    // Err with the reason when the file must not replace its source
+    // a failed measurement (e.g. a file without video) does not block
+    pub fn check(&self, config: &QualityConfig) -> Result<(), String> {
+        match self {
+            QualityScore::Measuring => Err("the quality is still being measured".to_string()),
+            QualityScore::Score(metric, score) if !config.passes(*metric, *score) => Err(format!(
+                "{} is below {}",
+                self.label(),
+                config.threshold(*metric)
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+// a rectangle of the source frame in pixels
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CropRect {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+// the part number in the output pattern of a segmented file
+const SEGMENT_NUMBER: &str = "_%03d";
+
+// the file of part `number` of a segment pattern
+pub fn segment_part(pattern: &Path, number: u32) -> PathBuf {
+    let name = pattern.file_name().unwrap_or_default().to_string_lossy();
+    pattern.with_file_name(name.replacen("%03d", &format!("{:03}", number), 1))
+}
+
+// the parts the segment muxer has written, numbered from 0
+pub fn segment_parts(pattern: &Path) -> Vec<PathBuf> {
+    (0..)
+        .map(|number| segment_part(pattern, number))
+        .take_while(|part| part.exists())
+        .collect()
+}
+
+// whether `path` is one of the parts of a segment pattern
+pub fn is_segment_part(pattern: &Path, path: &Path) -> bool {
+    let (Some(pattern_name), Some(name)) = (pattern.file_name(), path.file_name()) else {
+        return false;
+    };
+    let pattern_name = pattern_name.to_string_lossy();
+    let name = name.to_string_lossy();
+    let Some((prefix, suffix)) = pattern_name.split_once("%03d") else {
+        return false;
+    };
+    pattern.parent() == path.parent()
+        && name.len() >= prefix.len() + suffix.len() + 3
+        && name.starts_with(prefix)
+        && name.ends_with(suffix)
+        && name[prefix.len()..name.len() - suffix.len()]
+            .bytes()
+            .all(|byte| byte.is_ascii_digit())
+}
+
+// the cuts of one file, applied with every preset
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FileEdit {
+    pub start: Option<f64>, // the in point in seconds
+    pub end: Option<f64>,   // the out point in seconds
+    pub crop: Option<CropRect>,
+    pub segment: Option<u32>, // seconds of each part, the output becomes `<stem>_000.<ext>`, ...
+}
+
+impl FileEdit {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+
+    // the length of the output of a `source` seconds long file
+    pub fn duration(&self, source: f64) -> f64 {
+        let end = self.end.map_or(source, |end| end.min(source));
+        (end - self.start.unwrap_or(0.0)).max(0.0)
+    }
+
+    // the arguments merged into the preset by create_ffmpeg_command
+    // -ss and -to are input options, the crop goes first in the -vf chain
+    // `frames` is the output format of a hardware decoder, the crop runs on downloaded frames
+    pub fn args(&self, frames: Option<&str>) -> Vec<ArgKeyValue> {
+        let mut args = vec![];
+        if let Some(start) = self.start {
+            args.push(ArgKeyValue::new("-ss", &format!("{:.3}", start)));
+        }
+        if let Some(end) = self.end {
+            args.push(ArgKeyValue::new("-to", &format!("{:.3}", end)));
+        }
+        if let Some(crop) = self.crop {
+            let filter = format!("crop={}:{}:{}:{}", crop.width, crop.height, crop.x, crop.y);
+            args.push(ArgKeyValue::new("-vf", &software_filter(frames, &filter)));
+        }
+        if let Some(segment) = self.segment {
+            args.push(ArgKeyValue::new("-f", "segment"));
+            args.push(ArgKeyValue::new("-segment_time", &segment.to_string()));
+            args.push(ArgKeyValue::new("-reset_timestamps", "1"));
+        }
+        args
+    }
+
+    // the segment muxer numbers the parts of the output, ffmpeg writes to this pattern
+    pub fn pattern(&self, output: &Path) -> Option<PathBuf> {
+        self.segment?;
+        let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+        let name = match output.extension() {
+            Some(ext) => format!("{}{}.{}", stem, SEGMENT_NUMBER, ext.to_string_lossy()),
+            None => format!("{}{}", stem, SEGMENT_NUMBER),
+        };
+        Some(output.with_file_name(name))
+    }
+
+    pub fn summary(&self) -> String {
+        let mut parts = vec![];
+        if self.start.is_some() || self.end.is_some() {
+            parts.push(format!(
+                "trim {:.1}s-{}",
+                self.start.unwrap_or(0.0),
+                self.end
+                    .map_or("end".to_string(), |end| format!("{:.1}s", end))
+            ));
+        }
+        if let Some(crop) = self.crop {
+            parts.push(format!(
+                "crop {}x{}+{}+{}",
+                crop.width, crop.height, crop.x, crop.y
+            ));
+        }
+        if let Some(segment) = self.segment {
+            parts.push(format!("segments of {}s", segment));
+        }
+        parts.join(" ")
+    }
 }
 
 // whole files information
@@ -65,4 +370,263 @@ pub struct FilesState {
     pub lines: Vec<String>,                           // each line is a file path
     pub status: Vec<TaskStatus>,                      // status of each file
     pub progress: HashMap<usize, ProgressStatistics>, // progress of each file
+    #[serde(default)]
+    pub errors: HashMap<usize, String>, // the last error line of each failed file
+    #[serde(default)]
+    pub media: HashMap<usize, MediaInfo>, // ffprobe result of each file
+    #[serde(default)]
+    pub profiles: HashMap<usize, String>, // the preset chosen by the rules
+    #[serde(default)]
+    pub replaced: HashMap<usize, ReplaceRecord>, // how to undo the replace of each file
+    #[serde(default)]
+    pub outputs: HashMap<usize, PathBuf>, // the encoded file (first part) of each started task
+    #[serde(default)]
+    pub segments: HashMap<usize, PathBuf>, // the part pattern of each segmented task
+    #[serde(default)]
+    pub quality: HashMap<usize, QualityScore>, // the quality of each finished output
+    #[serde(default)]
+    pub edits: HashMap<usize, FileEdit>, // trim, crop and segments of each file
+    #[serde(default)]
+    pub partials: HashMap<usize, PartialOutput>, // what a running or interrupted task has written
+}
+
+impl FilesState {
+    // take lines out of the list, the lines after them move up
+    // returns the new index of each old line, None for the removed ones
+    pub fn remove_lines(&mut self, remove: &BTreeSet<usize>) -> Vec<Option<usize>> {
+        let mut mapping = Vec::with_capacity(self.lines.len());
+        let mut next = 0;
+        for index in 0..self.lines.len() {
+            if remove.contains(&index) {
+                mapping.push(None);
+            } else {
+                mapping.push(Some(next));
+                next += 1;
+            }
+        }
+        let keep = |index: &usize| !remove.contains(index);
+        self.lines = std::mem::take(&mut self.lines)
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| keep(index))
+            .map(|(_, line)| line)
+            .collect();
+        self.status = std::mem::take(&mut self.status)
+            .into_iter()
+            .enumerate()
+            .filter(|(index, _)| keep(index))
+            .map(|(_, status)| status)
+            .collect();
+        remap(&mut self.progress, &mapping);
+        remap(&mut self.errors, &mapping);
+        remap(&mut self.media, &mapping);
+        remap(&mut self.profiles, &mapping);
+        remap(&mut self.replaced, &mapping);
+        remap(&mut self.outputs, &mapping);
+        remap(&mut self.segments, &mapping);
+        remap(&mut self.quality, &mapping);
+        remap(&mut self.edits, &mapping);
+        remap(&mut self.partials, &mapping);
+        mapping
+    }
+
+    // the duration the output of a line should have
+    pub fn output_secs(&self, index: usize, source_secs: f64) -> f64 {
+        self.edits
+            .get(&index)
+            .map_or(source_secs, |edit| edit.duration(source_secs))
+    }
+
+    // an interrupted line goes on with `preset` after the readable part of its partial output
+    pub fn resumable(&self, index: usize, preset: &str) -> bool {
+        matches!(self.status.get(index), Some(TaskStatus::Interrupted))
+            && self
+                .partials
+                .get(&index)
+                .is_some_and(|partial| partial.preset == preset && partial.secs > 0.0)
+    }
+
+    // the quality gate of the replace buttons, only while the measurement is enabled
+    pub fn quality_check(&self, index: usize, config: &QualityConfig) -> Result<(), String> {
+        match self.quality.get(&index) {
+            Some(score) if config.enabled => score.check(config),
+            _ => Ok(()),
+        }
+    }
+}
+
+// move the values of a map keyed by line index to the new indexes
+pub fn remap<T>(map: &mut HashMap<usize, T>, mapping: &[Option<usize>]) {
+    *map = std::mem::take(map)
+        .into_iter()
+        .filter_map(|(index, value)| Some((mapping.get(index).copied().flatten()?, value)))
+        .collect();
+}
+
+// the file a task writes, kept so an interrupted encode can go on where it stopped
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PartialOutput {
+    pub preset: String, // the preset the file is encoded with
+    pub path: PathBuf,  // the staging file or the output, the first part of a resumed task
+    #[serde(default)]
+    pub secs: f64, // the readable length, measured by recover_lines
+}
+
+// everything needed to undo a replace
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReplaceRecord {
+    pub source: PathBuf, // the original path
+    pub trash: PathBuf,  // where the original is kept
+    pub target: PathBuf, // the encoded file next to the original
+    pub output: PathBuf, // the encoded file before it was moved
+    #[serde(default)]
+    pub sidecars: Vec<(PathBuf, PathBuf)>, // (sidecar, its copy in trash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{full_edit, pairs};
+
+    // one block of `ffmpeg -progress pipe:1`
+    const PROGRESS: &str = "frame=1450
+fps=48.31
+stream_0_0_q=28.0
+bitrate=1843.2kbits/s
+total_size=11141168
+out_time_us=48366000
+out_time=00:00:48.366000
+dup_frames=0
+drop_frames=0
+speed=1.61x
+progress=continue";
+
+    fn stats(block: &str) -> EncodeStats {
+        let mut stats = EncodeStats::default();
+        for (key, value) in block.lines().filter_map(|line| line.split_once('=')) {
+            stats.apply(key, value);
+        }
+        stats
+    }
+
+    #[test]
+    fn encode_stats_of_a_progress_block() {
+        let stats = stats(PROGRESS);
+        assert_eq!(stats.fps, 48.31);
+        assert_eq!(stats.speed, 1.61);
+        assert_eq!(stats.bitrate, 1843.2);
+        assert_eq!(stats.total_size, 11141168);
+    }
+
+    #[test]
+    fn encode_stats_keep_the_last_value_on_na() {
+        // the first block of a run, before anything is encoded
+        let stats = stats(&format!(
+            "{}\nfps=0.00\nbitrate=N/A\ntotal_size=N/A\nspeed=N/A",
+            PROGRESS
+        ));
+        assert_eq!(stats.fps, 0.0);
+        assert_eq!(stats.speed, 1.61);
+        assert_eq!(stats.bitrate, 1843.2);
+        assert_eq!(stats.total_size, 11141168);
+    }
+
+    #[test]
+    fn file_edit_args() {
+        assert!(FileEdit::default().args(None).is_empty());
+        assert_eq!(
+            pairs(&full_edit().args(None)),
+            [
+                ("-ss", "12.500"),
+                ("-to", "70.000"),
+                ("-vf", "crop=1280:720:100:40"),
+                ("-f", "segment"),
+                ("-segment_time", "20"),
+                ("-reset_timestamps", "1"),
+            ]
+        );
+    }
+
+    #[test]
+    fn file_edit_crop_of_hardware_frames() {
+        let crop = |frames| {
+            let args = full_edit().args(frames);
+            let crop = args.iter().find(|arg| arg.key == "-vf").unwrap();
+            crop.value.clone()
+        };
+        assert_eq!(
+            crop(Some("cuda")),
+            "hwdownload,format=nv12,crop=1280:720:100:40,hwupload_cuda"
+        );
+        assert_eq!(
+            crop(Some("vaapi")),
+            "hwdownload,format=nv12,crop=1280:720:100:40,hwupload"
+        );
+    }
+
+    #[test]
+    fn file_edit_duration() {
+        assert_eq!(FileEdit::default().duration(90.0), 90.0);
+        assert_eq!(full_edit().duration(90.0), 57.5);
+        // the out point is past the end of a shorter file
+        assert_eq!(full_edit().duration(30.0), 17.5);
+        assert_eq!(full_edit().duration(10.0), 0.0);
+    }
+
+    #[test]
+    fn file_edit_segment_pattern() {
+        let output = Path::new("/videos/out/movie.mp4");
+        assert_eq!(FileEdit::default().pattern(output), None);
+        let pattern = full_edit().pattern(output).unwrap();
+        assert_eq!(pattern, Path::new("/videos/out/movie_%03d.mp4"));
+        assert_eq!(
+            segment_part(&pattern, 7),
+            Path::new("/videos/out/movie_007.mp4")
+        );
+        assert!(is_segment_part(
+            &pattern,
+            Path::new("/videos/out/movie_012.mp4")
+        ));
+        assert!(!is_segment_part(
+            &pattern,
+            Path::new("/videos/out/movie_0a2.mp4")
+        ));
+        assert!(!is_segment_part(
+            &pattern,
+            Path::new("/videos/movie_000.mp4")
+        ));
+        assert!(!is_segment_part(
+            &pattern,
+            Path::new("/videos/out/movie.mp4")
+        ));
+    }
+
+    #[test]
+    fn only_the_preset_of_a_readable_partial_resumes() {
+        let mut state = FilesState {
+            lines: vec![
+                "/videos/movie.mkv".to_string(),
+                "/videos/clip.mkv".to_string(),
+            ],
+            status: vec![TaskStatus::Interrupted, TaskStatus::Interrupted],
+            ..Default::default()
+        };
+        let partial = |secs| PartialOutput {
+            preset: "h264".to_string(),
+            path: PathBuf::from("/videos/out/movie.mkv"),
+            secs,
+        };
+        state.partials.insert(0, partial(42.0));
+        state.partials.insert(1, partial(0.0));
+        assert!(state.resumable(0, "h264"));
+        assert!(!state.resumable(0, "hevc"));
+        // nothing readable was written
+        assert!(!state.resumable(1, "h264"));
+        state.status[0] = TaskStatus::Failed;
+        assert!(!state.resumable(0, "h264"));
+
+        let mapping = state.remove_lines(&BTreeSet::from([0]));
+        assert_eq!(mapping, vec![None, Some(0)]);
+        assert_eq!(state.partials[&0].secs, 0.0);
+    }
 }
@@ -1,6 +1,14 @@
-use bevy_ecs::resource::Resource;
+use bevy::ecs::resource::Resource;
+use bevy::log::warn;
 use serde::{Deserialize, Serialize};
 
+use super::hwaccel::{HwBackend, software_args, video_encoder};
+use super::probe::MediaInfo;
+
+// saved next to files_state.json
+pub const FFMPEG_ARG_FILE: &str = "ffmpeg_args.json";
+pub const PRESETS_FILE: &str = "presets.json";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ArgKeyValue {
     pub key: String,
@@ -15,12 +23,265 @@ impl ArgKeyValue {
     }
 }
 
+// the argument lists shown in the Setting state
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ArgGroup {
+    Preset(usize), // index of FfmpegArg::presets
+    Snapshot,
+    Analyze,
+}
+
+// the size of the output, the video bitrate is computed from the analyzed duration
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SizeTarget {
+    Megabytes(f64),
+    Ratio(f64), // of the source size, e.g. 0.5
+}
+
+impl std::fmt::Display for SizeTarget {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SizeTarget::Megabytes(mb) => write!(f, "{} MB", mb),
+            SizeTarget::Ratio(ratio) => write!(f, "{}% of the source", ratio * 100.0),
+        }
+    }
+}
+
+// the bitrate left for the container (interleaving, index)
+const MUX_OVERHEAD: f64 = 0.02;
+// below this the target is not reachable with a watchable video
+const MIN_VIDEO_KBPS: f64 = 50.0;
+
+impl SizeTarget {
+    // kbits/s of the video stream, the audio keeps `audio_kbps`
+    pub fn video_kbps(&self, media: &MediaInfo, audio_kbps: u64) -> Result<u64, String> {
+        if media.duration <= 0.0 {
+            return Err("the duration is unknown, analyze the file first".to_string());
+        }
+        let bytes = match self {
+            SizeTarget::Megabytes(mb) => mb * 1_000_000.0,
+            SizeTarget::Ratio(_) if media.size == 0 => {
+                return Err("the size of the source is unknown".to_string());
+            }
+            SizeTarget::Ratio(ratio) => ratio * media.size as f64,
+        };
+        let total_kbps = bytes * 8.0 / 1000.0 / media.duration * (1.0 - MUX_OVERHEAD);
+        let video_kbps = total_kbps - audio_kbps as f64;
+        if video_kbps < MIN_VIDEO_KBPS {
+            return Err(format!(
+                "{} is too small for {:.0} seconds",
+                self, media.duration
+            ));
+        }
+        Ok(video_kbps as u64)
+    }
+}
+
+// "2000k", "8M" or "128000" in kbits/s
+fn parse_kbps(value: &str) -> Option<u64> {
+    let value = value.trim();
+    if let Some(k) = value.strip_suffix(['k', 'K']) {
+        k.parse::<f64>().ok().map(|k| k as u64)
+    } else if let Some(m) = value.strip_suffix(['m', 'M']) {
+        m.parse::<f64>().ok().map(|m| (m * 1000.0) as u64)
+    } else {
+        value.parse::<f64>().ok().map(|bits| (bits / 1000.0) as u64)
+    }
+}
+
+// a named list of encoding arguments, each preset has a task button on every row
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Preset {
+    pub name: String,
+    pub args: Vec<ArgKeyValue>,
+    #[serde(default)]
+    pub hardware: bool, // the encoder is checked against `ffmpeg -encoders` at startup
+    #[serde(default = "default_ext")]
+    pub ext: String, // container extension of the output
+    #[serde(default)]
+    pub target: Option<SizeTarget>, // replaces the -b:v (or -crf) of the arguments
+    #[serde(default)]
+    pub two_pass: bool, // needs a software encoder
+}
+
+fn default_ext() -> String {
+    "mp4".to_string()
+}
+
+impl Preset {
+    pub fn new(name: &str, args: Vec<ArgKeyValue>) -> Self {
+        Self {
+            name: name.to_string(),
+            args,
+            hardware: false,
+            ext: default_ext(),
+            target: None,
+            two_pass: false,
+        }
+    }
+
+    pub fn with_target(self, target: SizeTarget, two_pass: bool) -> Self {
+        Self {
+            target: Some(target),
+            two_pass,
+            ..self
+        }
+    }
+
+    pub fn with_ext(self, ext: &str) -> Self {
+        Self {
+            ext: ext.to_string(),
+            ..self
+        }
+    }
+
+    pub fn hardware(name: &str, args: Vec<ArgKeyValue>) -> Self {
+        Self {
+            hardware: true,
+            ..Self::new(name, args)
+        }
+    }
+
+    // the value of the last argument with this key
+    fn arg(&self, key: &str) -> Option<&str> {
+        self.args
+            .iter()
+            .rev()
+            .find(|arg| arg.key == key)
+            .map(|arg| arg.value.as_str())
+    }
+
+    // the audio bitrate the output will have, in kbits/s
+    fn audio_kbps(&self, media: &MediaInfo) -> u64 {
+        if self.arg("-an").is_some() {
+            return 0;
+        }
+        match self.arg("-c:a") {
+            // ffmpeg keeps the first audio stream
+            None | Some("copy") => media.audio.first().map_or(0, |audio| audio.bitrate / 1000),
+            Some(_) => self.arg("-b:a").and_then(parse_kbps).unwrap_or(128),
+        }
+    }
+
+    // the arguments of a task, the size target is turned into a video bitrate
+    pub fn encode_args(&self, media: Option<&MediaInfo>) -> Result<Vec<ArgKeyValue>, String> {
+        let Some(target) = self.target else {
+            return Ok(self.args.clone());
+        };
+        let media = media.ok_or("the size target needs the duration, analyze the file first")?;
+        let video_kbps = target.video_kbps(media, self.audio_kbps(media))?;
+        let mut args: Vec<ArgKeyValue> = self
+            .args
+            .iter()
+            .filter(|arg| !matches!(arg.key.as_str(), "-b:v" | "-crf" | "-q:v"))
+            .cloned()
+            .collect();
+        // a lower -maxrate would keep the file below the target
+        for arg in args.iter_mut().filter(|arg| arg.key == "-maxrate") {
+            if parse_kbps(&arg.value).is_some_and(|maxrate| maxrate < video_kbps) {
+                arg.value = format!("{}k", video_kbps * 2);
+            }
+        }
+        args.push(ArgKeyValue::new("-b:v", &format!("{}k", video_kbps)));
+        Ok(args)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Resource)]
 pub struct FfmpegArg {
-    pub sf_convert: Vec<ArgKeyValue>,
-    pub hw_convert: Vec<ArgKeyValue>,
+    #[serde(skip)]
+    pub presets: Vec<Preset>, // saved in PRESETS_FILE
     pub snapshot: Vec<ArgKeyValue>,
     pub analyze: Vec<ArgKeyValue>,
+    #[serde(skip)]
+    pub changed: bool, // Flag to indicate the rows of the setting panel need to be rebuilt
+}
+
+impl FfmpegArg {
+    pub fn load() -> Self {
+        let mut ffmpeg_arg = match std::fs::read_to_string(FFMPEG_ARG_FILE) {
+            Ok(json) => serde_json::from_str(&json).unwrap_or_else(|e| {
+                warn!("Failed to deserialize {}: {}", FFMPEG_ARG_FILE, e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        };
+        if let Ok(json) = std::fs::read_to_string(PRESETS_FILE) {
+            match serde_json::from_str::<Vec<Preset>>(&json) {
+                Ok(presets) if !presets.is_empty() => ffmpeg_arg.presets = presets,
+                Ok(_) => warn!("{} has no presets", PRESETS_FILE),
+                Err(e) => warn!("Failed to deserialize {}: {}", PRESETS_FILE, e),
+            }
+        }
+        ffmpeg_arg
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(FFMPEG_ARG_FILE, json).map_err(|e| e.to_string())?;
+        let json = serde_json::to_string_pretty(&self.presets).map_err(|e| e.to_string())?;
+        std::fs::write(PRESETS_FILE, json).map_err(|e| e.to_string())
+    }
+
+    pub fn preset(&self, name: &str) -> Option<&Preset> {
+        self.presets.iter().find(|preset| preset.name == name)
+    }
+
+    pub fn preset_names(&self) -> Vec<String> {
+        self.presets
+            .iter()
+            .map(|preset| preset.name.clone())
+            .collect()
+    }
+
+    pub fn group(&self, group: ArgGroup) -> Option<&Vec<ArgKeyValue>> {
+        match group {
+            ArgGroup::Preset(index) => self.presets.get(index).map(|preset| &preset.args),
+            ArgGroup::Snapshot => Some(&self.snapshot),
+            ArgGroup::Analyze => Some(&self.analyze),
+        }
+    }
+
+    pub fn group_mut(&mut self, group: ArgGroup) -> Option<&mut Vec<ArgKeyValue>> {
+        match group {
+            ArgGroup::Preset(index) => self.presets.get_mut(index).map(|preset| &mut preset.args),
+            ArgGroup::Snapshot => Some(&mut self.snapshot),
+            ArgGroup::Analyze => Some(&mut self.analyze),
+        }
+    }
+
+    // hardware presets whose encoder is missing from this ffmpeg build are
+    // rebuilt for the detected backend, or fall back to software encode
+    // returns a message for the toast when a preset is changed
+    pub fn apply_hardware(
+        &mut self,
+        backend: Option<HwBackend>,
+        encoders: &[String],
+    ) -> Option<String> {
+        let mut changed = vec![];
+        for preset in self.presets.iter_mut().filter(|preset| preset.hardware) {
+            let available = video_encoder(&preset.args)
+                .is_some_and(|encoder| encoders.iter().any(|name| name == encoder));
+            if available {
+                continue;
+            }
+            preset.args = match backend {
+                Some(backend) => backend.args(),
+                None => software_args(),
+            };
+            changed.push(preset.name.clone());
+        }
+        if changed.is_empty() {
+            return None;
+        }
+        Some(match backend {
+            Some(backend) => format!("{} uses {} encoding", changed.join(", "), backend),
+            None => format!(
+                "No hardware encoder found, {} falls back to software encoding",
+                changed.join(", ")
+            ),
+        })
+    }
 }
 
 impl Default for FfmpegArg {
@@ -55,6 +316,58 @@ impl Default for FfmpegArg {
             sf_convert.push(ArgKeyValue::new("-progress", "pipe:1"));
             sf_convert.push(ArgKeyValue::new("-pix_fmt", "yuv420p"));
         }
+        let mut hevc_720p = vec![];
+        {
+            hevc_720p.push(ArgKeyValue::new("-nostdin", ""));
+            hevc_720p.push(ArgKeyValue::new("-vf", "scale=-2:720"));
+            hevc_720p.push(ArgKeyValue::new("-c:v", "libx265"));
+            hevc_720p.push(ArgKeyValue::new("-crf", "26"));
+            hevc_720p.push(ArgKeyValue::new("-preset", "medium"));
+            hevc_720p.push(ArgKeyValue::new("-c:a", "copy"));
+            hevc_720p.push(ArgKeyValue::new("-tag:v", "hvc1"));
+            hevc_720p.push(ArgKeyValue::new("-loglevel", "info"));
+            hevc_720p.push(ArgKeyValue::new("-progress", "pipe:1"));
+        }
+        let mut archive_av1 = vec![];
+        {
+            archive_av1.push(ArgKeyValue::new("-nostdin", ""));
+            archive_av1.push(ArgKeyValue::new("-c:v", "libsvtav1"));
+            archive_av1.push(ArgKeyValue::new("-crf", "30"));
+            archive_av1.push(ArgKeyValue::new("-preset", "6"));
+            archive_av1.push(ArgKeyValue::new("-c:a", "copy"));
+            archive_av1.push(ArgKeyValue::new("-loglevel", "info"));
+            archive_av1.push(ArgKeyValue::new("-progress", "pipe:1"));
+        }
+        // -b:v is computed from the size target of the preset
+        let mut target_size = vec![];
+        {
+            target_size.push(ArgKeyValue::new("-nostdin", ""));
+            target_size.push(ArgKeyValue::new("-c:v", "libx264"));
+            target_size.push(ArgKeyValue::new("-preset", "slow"));
+            target_size.push(ArgKeyValue::new("-c:a", "aac"));
+            target_size.push(ArgKeyValue::new("-b:a", "128k"));
+            target_size.push(ArgKeyValue::new("-pix_fmt", "yuv420p"));
+            target_size.push(ArgKeyValue::new("-loglevel", "info"));
+            target_size.push(ArgKeyValue::new("-progress", "pipe:1"));
+        }
+        let mut audio_aac = vec![];
+        {
+            audio_aac.push(ArgKeyValue::new("-nostdin", ""));
+            audio_aac.push(ArgKeyValue::new("-vn", ""));
+            audio_aac.push(ArgKeyValue::new("-c:a", "aac"));
+            audio_aac.push(ArgKeyValue::new("-b:a", "192k"));
+            audio_aac.push(ArgKeyValue::new("-loglevel", "info"));
+            audio_aac.push(ArgKeyValue::new("-progress", "pipe:1"));
+        }
+        let presets = vec![
+            Preset::hardware("hw", hw_convert),
+            Preset::new("sf", sf_convert),
+            Preset::new("720p HEVC", hevc_720p),
+            Preset::new("archive AV1", archive_av1),
+            Preset::new("700MB two-pass", target_size)
+                .with_target(SizeTarget::Megabytes(700.0), true),
+            Preset::new("audio-only AAC", audio_aac).with_ext("m4a"),
+        ];
 
         let mut snapshot = vec![];
         {
@@ -68,18 +381,118 @@ impl Default for FfmpegArg {
         let mut analyze = vec![];
         {
             analyze.push(ArgKeyValue::new("-v", "error"));
-            analyze.push(ArgKeyValue::new("-show_entries", "format=duration"));
-            analyze.push(ArgKeyValue::new(
-                "-of",
-                "default=noprint_wrappers=1:nokey=1",
-            ));
+            analyze.push(ArgKeyValue::new("-print_format", "json"));
+            analyze.push(ArgKeyValue::new("-show_format", ""));
+            analyze.push(ArgKeyValue::new("-show_streams", ""));
         }
 
         Self {
-            sf_convert,
-            hw_convert,
+            presets,
             snapshot,
             analyze,
+            changed: false,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{movie, pairs};
+
+    #[test]
+    fn video_kbps_of_a_size() {
+        // 700 MB over 5600 s is 1000 kbits/s, 2% for the container, 128 for the audio
+        assert_eq!(
+            SizeTarget::Megabytes(700.0).video_kbps(&movie(), 128),
+            Ok(852)
+        );
+        assert_eq!(
+            SizeTarget::Megabytes(700.0).video_kbps(&movie(), 0),
+            Ok(980)
+        );
+    }
+
+    #[test]
+    fn video_kbps_of_a_ratio() {
+        assert_eq!(SizeTarget::Ratio(0.5).video_kbps(&movie(), 128), Ok(1832));
+    }
+
+    #[test]
+    fn video_kbps_errors() {
+        let unknown = MediaInfo {
+            duration: 0.0,
+            ..movie()
+        };
+        assert!(
+            SizeTarget::Megabytes(700.0)
+                .video_kbps(&unknown, 128)
+                .is_err()
+        );
+        let no_size = MediaInfo { size: 0, ..movie() };
+        assert!(SizeTarget::Ratio(0.5).video_kbps(&no_size, 128).is_err());
+        // 14 kbits/s is left for the video
+        let error = SizeTarget::Megabytes(10.0)
+            .video_kbps(&movie(), 0)
+            .unwrap_err();
+        assert_eq!(error, "10 MB is too small for 5600 seconds");
+    }
+
+    #[test]
+    fn encode_args_without_target() {
+        let preset = Preset::new("copy", vec![ArgKeyValue::new("-c", "copy")]);
+        assert_eq!(pairs(&preset.encode_args(None).unwrap()), [("-c", "copy")]);
+    }
+
+    #[test]
+    fn encode_args_replace_the_rate_control() {
+        let preset = Preset::new(
+            "target",
+            vec![
+                ArgKeyValue::new("-c:v", "libx264"),
+                ArgKeyValue::new("-crf", "23"),
+                ArgKeyValue::new("-maxrate", "500k"),
+                ArgKeyValue::new("-c:a", "aac"),
+                ArgKeyValue::new("-b:a", "96k"),
+            ],
+        )
+        .with_target(SizeTarget::Megabytes(700.0), true);
+        let args = preset.encode_args(Some(&movie())).unwrap();
+        assert_eq!(
+            pairs(&args),
+            [
+                ("-c:v", "libx264"),
+                ("-maxrate", "1768k"),
+                ("-c:a", "aac"),
+                ("-b:a", "96k"),
+                ("-b:v", "884k"),
+            ]
+        );
+        assert!(preset.encode_args(None).is_err());
+    }
+
+    #[test]
+    fn encode_args_audio_bitrate() {
+        let target =
+            |args| Preset::new("target", args).with_target(SizeTarget::Megabytes(700.0), false);
+        let video_bitrate = |preset: Preset| {
+            let args = preset.encode_args(Some(&movie())).unwrap();
+            args.last().map(|arg| arg.value.clone()).unwrap()
+        };
+        // the copied audio keeps the bitrate of the source
+        assert_eq!(video_bitrate(target(vec![])), "852k");
+        assert_eq!(
+            video_bitrate(target(vec![ArgKeyValue::new("-c:a", "copy")])),
+            "852k"
+        );
+        // an encoder without -b:a is counted at 128k
+        assert_eq!(
+            video_bitrate(target(vec![ArgKeyValue::new("-c:a", "libopus")])),
+            "852k"
+        );
+        assert_eq!(
+            video_bitrate(target(vec![ArgKeyValue::new("-an", "")])),
+            "980k"
+        );
+    }
+}
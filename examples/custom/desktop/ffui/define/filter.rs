@@ -0,0 +1,173 @@
+use bevy::prelude::*;
+use std::cmp::Ordering;
+use std::path::Path;
+
+use super::custom::{FilesState, TaskStatus};
+
+// the rows shown by the status filter
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum StatusFilter {
+    #[default]
+    All,
+    Unfinished, // everything but done and replaced
+    Waiting,    // waiting and queued
+    Running,
+    Done,
+    Replaced,
+    Failed, // failed, cancelled and interrupted
+}
+
+impl StatusFilter {
+    pub const ALL: [StatusFilter; 7] = [
+        StatusFilter::All,
+        StatusFilter::Unfinished,
+        StatusFilter::Waiting,
+        StatusFilter::Running,
+        StatusFilter::Done,
+        StatusFilter::Replaced,
+        StatusFilter::Failed,
+    ];
+
+    pub fn matches(&self, status: &TaskStatus) -> bool {
+        match self {
+            StatusFilter::All => true,
+            StatusFilter::Unfinished => !matches!(status, TaskStatus::Done | TaskStatus::Replaced),
+            StatusFilter::Waiting => matches!(status, TaskStatus::Waiting | TaskStatus::Queued),
+            StatusFilter::Running => matches!(status, TaskStatus::Running),
+            StatusFilter::Done => matches!(status, TaskStatus::Done),
+            StatusFilter::Replaced => matches!(status, TaskStatus::Replaced),
+            StatusFilter::Failed => matches!(
+                status,
+                TaskStatus::Failed | TaskStatus::Cancelled | TaskStatus::Interrupted
+            ),
+        }
+    }
+}
+
+impl std::fmt::Display for StatusFilter {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            StatusFilter::All => write!(f, "all"),
+            StatusFilter::Unfinished => write!(f, "unfinished"),
+            StatusFilter::Waiting => write!(f, "waiting"),
+            StatusFilter::Running => write!(f, "running"),
+            StatusFilter::Done => write!(f, "done"),
+            StatusFilter::Replaced => write!(f, "replaced"),
+            StatusFilter::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+// the column the rows are sorted by
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    #[default]
+    Index, // import order
+    Name,
+    Duration, // ProgressStatistics.total
+    Size,     // size of the source file
+}
+
+impl SortKey {
+    pub const ALL: [SortKey; 4] = [
+        SortKey::Index,
+        SortKey::Name,
+        SortKey::Duration,
+        SortKey::Size,
+    ];
+}
+
+impl std::fmt::Display for SortKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SortKey::Index => write!(f, "index"),
+            SortKey::Name => write!(f, "name"),
+            SortKey::Duration => write!(f, "duration"),
+            SortKey::Size => write!(f, "size"),
+        }
+    }
+}
+
+// the status and the sort values of the lines the list was filtered with
+#[derive(Debug, Default, PartialEq)]
+pub struct FilterInputs {
+    status: Vec<TaskStatus>,
+    sort_values: Vec<u64>,
+}
+
+// the search, filter and sort of the filter bar
+#[derive(Debug, Default, Resource)]
+pub struct ListFilter {
+    pub search: String, // case-insensitive part of the path
+    pub status: StatusFilter,
+    pub sort: SortKey,
+    pub descending: bool,
+}
+
+impl ListFilter {
+    // the indexes of the lines to show, in display order
+    pub fn apply(&self, state: &FilesState) -> Vec<usize> {
+        let search = self.search.to_lowercase();
+        let mut shown: Vec<usize> = (0..state.lines.len())
+            .filter(|index| {
+                state
+                    .status
+                    .get(*index)
+                    .is_some_and(|status| self.status.matches(status))
+            })
+            .filter(|index| {
+                search.is_empty() || state.lines[*index].to_lowercase().contains(&search)
+            })
+            .collect();
+        // stable, equal rows keep the import order
+        shown.sort_by(|a, b| {
+            let ordering = self.compare(state, *a, *b);
+            if self.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+        shown
+    }
+
+    // what apply reads from the state besides the lines, the list is filtered again
+    // only when these change (a progress tick does not)
+    pub fn inputs(&self, state: &FilesState) -> FilterInputs {
+        let sort_values = match self.sort {
+            SortKey::Index | SortKey::Name => vec![],
+            SortKey::Duration => (0..state.lines.len())
+                .map(|index| state.progress.get(&index).map_or(0, |p| p.total))
+                .collect(),
+            SortKey::Size => (0..state.lines.len())
+                .map(|index| state.media.get(&index).map_or(0, |m| m.size))
+                .collect(),
+        };
+        FilterInputs {
+            status: state.status.clone(),
+            sort_values,
+        }
+    }
+
+    fn compare(&self, state: &FilesState, a: usize, b: usize) -> Ordering {
+        match self.sort {
+            SortKey::Index => a.cmp(&b),
+            SortKey::Name => file_name(&state.lines[a]).cmp(&file_name(&state.lines[b])),
+            SortKey::Duration => {
+                let total = |index| state.progress.get(&index).map_or(0, |p| p.total);
+                total(a).cmp(&total(b))
+            }
+            SortKey::Size => {
+                let size = |index| state.media.get(&index).map_or(0, |m| m.size);
+                size(a).cmp(&size(b))
+            }
+        }
+    }
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path).file_name().map_or_else(
+        || path.to_lowercase(),
+        |name| name.to_string_lossy().to_lowercase(),
+    )
+}
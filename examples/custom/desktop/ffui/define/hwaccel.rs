@@ -0,0 +1,181 @@
+use super::ffmpeg_arg::ArgKeyValue;
+
+// the render node of the VAAPI preset, another gpu can be chosen with FFUI_VAAPI_DEVICE
+pub fn vaapi_device() -> String {
+    std::env::var("FFUI_VAAPI_DEVICE").unwrap_or_else(|_| "/dev/dri/renderD128".to_string())
+}
+
+// the hardware encoders ffui knows how to drive, in order of preference
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HwBackend {
+    VideoToolbox, // macOS
+    Nvenc,        // NVIDIA
+    Qsv,          // Intel Quick Sync
+    Vaapi,        // Intel / AMD on Linux
+}
+
+impl HwBackend {
+    pub const ALL: [HwBackend; 4] = [
+        HwBackend::VideoToolbox,
+        HwBackend::Nvenc,
+        HwBackend::Qsv,
+        HwBackend::Vaapi,
+    ];
+
+    // the name listed by `ffmpeg -hwaccels`
+    pub fn hwaccel(&self) -> &'static str {
+        match self {
+            HwBackend::VideoToolbox => "videotoolbox",
+            HwBackend::Nvenc => "cuda",
+            HwBackend::Qsv => "qsv",
+            HwBackend::Vaapi => "vaapi",
+        }
+    }
+
+    // the name listed by `ffmpeg -encoders`
+    pub fn encoder(&self) -> &'static str {
+        match self {
+            HwBackend::VideoToolbox => "hevc_videotoolbox",
+            HwBackend::Nvenc => "hevc_nvenc",
+            HwBackend::Qsv => "hevc_qsv",
+            HwBackend::Vaapi => "hevc_vaapi",
+        }
+    }
+
+    // the arguments of the hardware preset
+    pub fn args(&self) -> Vec<ArgKeyValue> {
+        let mut args = vec![];
+        match self {
+            HwBackend::VideoToolbox => {
+                args.push(ArgKeyValue::new("-hwaccel", "videotoolbox"));
+                args.push(ArgKeyValue::new("-nostdin", ""));
+                args.push(ArgKeyValue::new("-vf", "scale=-2:720,fps=30"));
+                args.push(ArgKeyValue::new("-c:v", "hevc_videotoolbox"));
+                args.push(ArgKeyValue::new("-quality", "high"));
+            }
+            HwBackend::Nvenc => {
+                args.push(ArgKeyValue::new("-hwaccel", "cuda"));
+                args.push(ArgKeyValue::new("-hwaccel_output_format", "cuda"));
+                args.push(ArgKeyValue::new("-nostdin", ""));
+                args.push(ArgKeyValue::new("-vf", "scale_cuda=-2:720,fps=30"));
+                args.push(ArgKeyValue::new("-c:v", "hevc_nvenc"));
+                args.push(ArgKeyValue::new("-preset", "p5"));
+            }
+            HwBackend::Qsv => {
+                args.push(ArgKeyValue::new("-hwaccel", "qsv"));
+                args.push(ArgKeyValue::new("-hwaccel_output_format", "qsv"));
+                args.push(ArgKeyValue::new("-nostdin", ""));
+                args.push(ArgKeyValue::new("-vf", "scale_qsv=-2:720,fps=30"));
+                args.push(ArgKeyValue::new("-c:v", "hevc_qsv"));
+                args.push(ArgKeyValue::new("-preset", "medium"));
+            }
+            HwBackend::Vaapi => {
+                args.push(ArgKeyValue::new("-hwaccel", "vaapi"));
+                args.push(ArgKeyValue::new("-hwaccel_output_format", "vaapi"));
+                args.push(ArgKeyValue::new("-vaapi_device", &vaapi_device()));
+                args.push(ArgKeyValue::new("-nostdin", ""));
+                args.push(ArgKeyValue::new("-vf", "scale_vaapi=w=-2:h=720,fps=30"));
+                args.push(ArgKeyValue::new("-c:v", "hevc_vaapi"));
+            }
+        }
+        args.push(ArgKeyValue::new("-b:v", "2000k"));
+        args.push(ArgKeyValue::new("-maxrate", "8000k"));
+        args.push(ArgKeyValue::new("-bufsize", "16000k"));
+        args.push(ArgKeyValue::new("-c:a", "copy"));
+        args.push(ArgKeyValue::new("-tag:v", "hvc1"));
+        args.push(ArgKeyValue::new("-loglevel", "info"));
+        args.push(ArgKeyValue::new("-progress", "pipe:1"));
+        args
+    }
+
+    // the first backend that ffmpeg supports both as hwaccel and encoder
+    pub fn choose(hwaccels: &[String], encoders: &[String]) -> Option<HwBackend> {
+        HwBackend::ALL.into_iter().find(|backend| {
+            hwaccels.iter().any(|name| name == backend.hwaccel())
+                && encoders.iter().any(|name| name == backend.encoder())
+        })
+    }
+}
+
+impl std::fmt::Display for HwBackend {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.hwaccel())
+    }
+}
+
+// software encode, used when no hardware backend is available
+pub fn software_args() -> Vec<ArgKeyValue> {
+    vec![
+        ArgKeyValue::new("-nostdin", ""),
+        ArgKeyValue::new("-vf", "scale=-2:720,fps=30"),
+        ArgKeyValue::new("-c:v", "libx265"),
+        ArgKeyValue::new("-crf", "26"),
+        ArgKeyValue::new("-c:a", "copy"),
+        ArgKeyValue::new("-tag:v", "hvc1"),
+        ArgKeyValue::new("-loglevel", "info"),
+        ArgKeyValue::new("-progress", "pipe:1"),
+    ]
+}
+
+// output of `ffmpeg -hide_banner -hwaccels`:
+//
+// Hardware acceleration methods:
+// vdpau
+// cuda
+// vaapi
+pub fn parse_hwaccels(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.ends_with(':'))
+        .map(str::to_string)
+        .collect()
+}
+
+// output of `ffmpeg -hide_banner -encoders`:
+//
+// Encoders:
+//  V..... = Video
+//  ...
+//  ------
+//  V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)
+//  V....D hevc_vaapi           H.265/HEVC (VAAPI) (codec hevc)
+pub fn parse_encoders(output: &str) -> Vec<String> {
+    output
+        .lines()
+        .skip_while(|line| !line.trim_start().starts_with("---"))
+        .skip(1)
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            let flags = columns.next()?;
+            let name = columns.next()?;
+            (flags.len() == 6).then(|| name.to_string())
+        })
+        .collect()
+}
+
+// the video encoder of an argument list
+pub fn video_encoder(args: &[ArgKeyValue]) -> Option<&str> {
+    args.iter()
+        .find(|arg| arg.key == "-c:v" || arg.key == "-vcodec")
+        .map(|arg| arg.value.as_str())
+}
+
+// the format of the decoded frames when they stay on the gpu (`-hwaccel_output_format`)
+pub fn hw_frames(args: &[ArgKeyValue]) -> Option<&str> {
+    args.iter()
+        .find(|arg| arg.key == "-hwaccel_output_format")
+        .map(|arg| arg.value.as_str())
+}
+
+// a software filter in a chain of hardware frames, the frames are downloaded before it
+// and uploaded again for the hardware scaler and encoder
+pub fn software_filter(frames: Option<&str>, filter: &str) -> String {
+    let upload = match frames {
+        None => return filter.to_string(),
+        Some("cuda") => "hwupload_cuda",
+        Some("qsv") => "hwupload=extra_hw_frames=64",
+        Some(_) => "hwupload",
+    };
+    format!("hwdownload,format=nv12,{},{}", filter, upload)
+}
@@ -1,5 +1,8 @@
 use std::any::Any;
 
+use super::config::{QualityConfig, QualityMetric};
+use super::filter::{SortKey, StatusFilter};
+
 pub trait MenuButtonNext: std::fmt::Display {
     fn next(&mut self) -> bool {
         false
@@ -53,6 +56,115 @@ impl MenuButtonNext for MenuImportButton {
     }
 }
 
+// import files button, opens the file dialog
+#[derive(Debug, Default)]
+pub struct MenuImportFilesButton;
+impl std::fmt::Display for MenuImportFilesButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Import")
+    }
+}
+impl MenuButtonNext for MenuImportFilesButton {}
+
+// the actions on the selected lines
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BulkAction {
+    Start,    // queue with the preset of the menu
+    Cancel,   // take out of the queue or interrupt
+    Replace,  // replace the sources of the done lines
+    Remove,   // take out of the list
+    Snapshot, // one snapshot of each line in a grid
+    Analyze,  // run ffprobe again
+    Retry,    // queue the failed lines again
+}
+
+// bulk action button of the selection bar
+#[derive(Debug)]
+pub struct MenuBulkButton(pub BulkAction);
+impl std::fmt::Display for MenuBulkButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.0 {
+            BulkAction::Start => write!(f, "Start"),
+            BulkAction::Cancel => write!(f, "Cancel"),
+            BulkAction::Replace => write!(f, "Replace"),
+            BulkAction::Remove => write!(f, "Remove"),
+            BulkAction::Snapshot => write!(f, "Snapshots"),
+            BulkAction::Analyze => write!(f, "Analyze"),
+            BulkAction::Retry => write!(f, "Retry failed"),
+        }
+    }
+}
+impl MenuButtonNext for MenuBulkButton {}
+
+// contact sheet button, the snapshot buttons make a sheet of many frames
+#[derive(Debug, Default)]
+pub struct MenuSheetButton {
+    pub checked: bool,
+}
+impl std::fmt::Display for MenuSheetButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sheet")
+    }
+}
+impl MenuButtonNext for MenuSheetButton {
+    fn next(&mut self) -> bool {
+        self.checked = !self.checked;
+        self.checked
+    }
+}
+
+// measure the quality of each output, a low score blocks the replace
+#[derive(Debug, Default)]
+pub struct MenuQualityButton {
+    pub checked: bool,
+}
+impl std::fmt::Display for MenuQualityButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Quality")
+    }
+}
+impl MenuButtonNext for MenuQualityButton {
+    fn next(&mut self) -> bool {
+        self.checked = !self.checked;
+        self.checked
+    }
+}
+
+// the lowest quality score that may replace its source
+#[derive(Debug, Clone, Copy)]
+pub struct MenuThresholdButton {
+    pub metric: QualityMetric,
+    pub threshold: f64,
+}
+impl MenuThresholdButton {
+    pub fn new(config: &QualityConfig) -> Self {
+        Self {
+            metric: config.metric,
+            threshold: config.threshold(config.metric),
+        }
+    }
+}
+impl std::fmt::Display for MenuThresholdButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.metric {
+            QualityMetric::Ssim => write!(f, "Min SSIM: {:.2}", self.threshold),
+            metric => write!(f, "Min {}: {:.0}", metric.name(), self.threshold),
+        }
+    }
+}
+impl MenuButtonNext for MenuThresholdButton {
+    fn next(&mut self) -> bool {
+        let steps = self.metric.threshold_steps();
+        // a threshold from config.json between the steps moves to the next step
+        self.threshold = steps
+            .iter()
+            .copied()
+            .find(|step| *step > self.threshold + 1e-9)
+            .unwrap_or(steps[0]);
+        false
+    }
+}
+
 // save button
 #[derive(Debug, Default)]
 pub struct MenuSaveButton;
@@ -83,23 +195,147 @@ impl std::fmt::Display for MenuClearButton {
 }
 impl MenuButtonNext for MenuClearButton {}
 
-// hide button
+// status filter button
 #[derive(Debug, Default)]
-pub struct MenuHideButton {
+pub struct MenuFilterButton(pub StatusFilter);
+impl std::fmt::Display for MenuFilterButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Show: {}", self.0)
+    }
+}
+impl MenuButtonNext for MenuFilterButton {
+    fn next(&mut self) -> bool {
+        let index = StatusFilter::ALL.iter().position(|s| *s == self.0);
+        self.0 = StatusFilter::ALL[index.map_or(0, |i| (i + 1) % StatusFilter::ALL.len())];
+        false
+    }
+}
+
+// sort column button
+#[derive(Debug, Default)]
+pub struct MenuSortButton(pub SortKey);
+impl std::fmt::Display for MenuSortButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Sort: {}", self.0)
+    }
+}
+impl MenuButtonNext for MenuSortButton {
+    fn next(&mut self) -> bool {
+        let index = SortKey::ALL.iter().position(|s| *s == self.0);
+        self.0 = SortKey::ALL[index.map_or(0, |i| (i + 1) % SortKey::ALL.len())];
+        false
+    }
+}
+
+// sort order button
+#[derive(Debug, Default)]
+pub struct MenuOrderButton {
+    pub descending: bool,
+}
+impl std::fmt::Display for MenuOrderButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.descending {
+            write!(f, "Desc")
+        } else {
+            write!(f, "Asc")
+        }
+    }
+}
+impl MenuButtonNext for MenuOrderButton {
+    fn next(&mut self) -> bool {
+        self.descending = !self.descending;
+        self.descending
+    }
+}
+
+// start all waiting tasks button
+#[derive(Debug, Default)]
+pub struct MenuStartAllButton;
+impl std::fmt::Display for MenuStartAllButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Start All")
+    }
+}
+impl MenuButtonNext for MenuStartAllButton {}
+
+// preset picker button, the preset used by Start All
+#[derive(Debug, Default, Clone)]
+pub struct MenuPresetButton {
+    pub names: Vec<String>,
+    pub index: usize,
+}
+impl MenuPresetButton {
+    pub fn new(names: Vec<String>) -> Self {
+        Self { names, index: 0 }
+    }
+    pub fn current(&self) -> &str {
+        self.names.get(self.index).map_or("", |name| name.as_str())
+    }
+}
+impl std::fmt::Display for MenuPresetButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Preset: {}", self.current())
+    }
+}
+impl MenuButtonNext for MenuPresetButton {
+    fn next(&mut self) -> bool {
+        if !self.names.is_empty() {
+            self.index = (self.index + 1) % self.names.len();
+        }
+        false
+    }
+}
+
+// pause queue button
+#[derive(Debug, Default)]
+pub struct MenuPauseButton {
     pub checked: bool,
 }
-impl std::fmt::Display for MenuHideButton {
+impl std::fmt::Display for MenuPauseButton {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "Hide")
+        if self.checked {
+            write!(f, "Resume")
+        } else {
+            write!(f, "Pause")
+        }
     }
 }
-impl MenuButtonNext for MenuHideButton {
+impl MenuButtonNext for MenuPauseButton {
     fn next(&mut self) -> bool {
         self.checked = !self.checked;
         self.checked
     }
 }
 
+// max parallel tasks button
+#[derive(Debug, Clone, Copy)]
+pub struct MenuParallelButton {
+    pub count: usize,
+}
+impl MenuParallelButton {
+    pub const MAX: usize = 4;
+}
+impl Default for MenuParallelButton {
+    fn default() -> Self {
+        Self { count: 1 }
+    }
+}
+impl std::fmt::Display for MenuParallelButton {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Parallel: {}", self.count)
+    }
+}
+impl MenuButtonNext for MenuParallelButton {
+    fn next(&mut self) -> bool {
+        self.count = if self.count >= Self::MAX {
+            1
+        } else {
+            self.count + 1
+        };
+        false
+    }
+}
+
 // setting button
 #[derive(Debug, Default)]
 pub struct MenuToggleSetting {
@@ -13,6 +13,21 @@ pub use menu::*;
 pub mod ffmpeg_arg;
 pub use ffmpeg_arg::*;
 
+pub mod probe;
+pub use probe::*;
+
+pub mod rules;
+pub use rules::*;
+
+pub mod hwaccel;
+pub use hwaccel::*;
+
+pub mod config;
+pub use config::*;
+
+pub mod filter;
+pub use filter::*;
+
 use tokio::io::BufReader;
 use tokio::process::{Child, Command};
 
@@ -38,11 +53,17 @@ impl ManagedProcess {
     pub fn stderr(&mut self) -> BufReader<tokio::process::ChildStderr> {
         BufReader::new(self.child.stderr.take().unwrap())
     }
+
+    // kill the process and wait until it exits (call from a non-runtime thread)
+    pub fn kill(&mut self) -> std::io::Result<()> {
+        TOKIO_RT.block_on(async { self.child.kill().await })
+    }
 }
 
 impl Drop for ManagedProcess {
     fn drop(&mut self) {
-        let _ = self.child.kill(); // 尝试终止进程
-        let _ = self.child.wait(); // 等待进程结束
+        // the drop may happen inside the runtime, so only send the signal here
+        let _ = self.child.start_kill(); // 尝试终止进程
+        let _ = self.child.try_wait(); // 回收已结束的进程
     }
 }
@@ -0,0 +1,238 @@
+use serde::{Deserialize, Serialize};
+
+// the output of `ffprobe -print_format json -show_format -show_streams`
+// ffprobe writes most numbers as strings, so they are parsed afterwards
+#[derive(Debug, Deserialize)]
+struct FfprobeOutput {
+    #[serde(default)]
+    format: Option<FfprobeFormat>,
+    #[serde(default)]
+    streams: Vec<FfprobeStream>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeFormat {
+    format_name: Option<String>,
+    duration: Option<String>,
+    size: Option<String>,
+    bit_rate: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeStream {
+    codec_type: Option<String>,
+    codec_name: Option<String>,
+    width: Option<u32>,
+    height: Option<u32>,
+    avg_frame_rate: Option<String>,
+    r_frame_rate: Option<String>,
+    pix_fmt: Option<String>,
+    color_transfer: Option<String>,
+    color_primaries: Option<String>,
+    bit_rate: Option<String>,
+    channels: Option<u32>,
+    sample_rate: Option<String>,
+    #[serde(default)]
+    tags: Option<FfprobeTags>,
+    #[serde(default)]
+    disposition: Option<FfprobeDisposition>,
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeDisposition {
+    #[serde(default)]
+    attached_pic: u32, // 1 for a cover image, it is stored as a video stream
+}
+
+#[derive(Debug, Deserialize)]
+struct FfprobeTags {
+    language: Option<String>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct VideoStream {
+    pub codec: String,
+    pub width: u32,
+    pub height: u32,
+    pub frame_rate: f64,
+    pub pix_fmt: String,
+    pub hdr: bool,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct AudioStream {
+    pub codec: String,
+    pub channels: u32,
+    pub sample_rate: u32,
+    pub bitrate: u64,
+    pub language: String,
+}
+
+// media information of each file
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct MediaInfo {
+    pub container: String,
+    pub duration: f64, // seconds
+    pub size: u64,     // bytes
+    pub bitrate: u64,  // bits/s
+    pub video: Option<VideoStream>,
+    pub audio: Vec<AudioStream>,
+}
+
+impl MediaInfo {
+    // parse the json output of ffprobe
+    pub fn from_ffprobe(json: &str) -> Result<Self, serde_json::Error> {
+        let output: FfprobeOutput = serde_json::from_str(json)?;
+        let mut info = MediaInfo::default();
+
+        if let Some(format) = output.format {
+            info.container = format.format_name.unwrap_or_default();
+            info.duration = parse_number(format.duration.as_deref());
+            info.size = parse_number(format.size.as_deref()) as u64;
+            info.bitrate = parse_number(format.bit_rate.as_deref()) as u64;
+        }
+
+        for stream in output.streams {
+            // cover art is not part of the video, an mp3 or m4a with a cover has no video
+            if stream
+                .disposition
+                .as_ref()
+                .is_some_and(|disposition| disposition.attached_pic == 1)
+            {
+                continue;
+            }
+            match stream.codec_type.as_deref() {
+                // the first video stream is the main one
+                Some("video") if info.video.is_none() => {
+                    let frame_rate = match parse_rate(stream.avg_frame_rate.as_deref()) {
+                        rate if rate > 0.0 => rate,
+                        _ => parse_rate(stream.r_frame_rate.as_deref()),
+                    };
+                    // PQ (HDR10) or HLG transfer, or BT.2020 primaries
+                    let hdr = matches!(
+                        stream.color_transfer.as_deref(),
+                        Some("smpte2084") | Some("arib-std-b67")
+                    ) || stream.color_primaries.as_deref() == Some("bt2020");
+                    info.video = Some(VideoStream {
+                        codec: stream.codec_name.unwrap_or_default(),
+                        width: stream.width.unwrap_or(0),
+                        height: stream.height.unwrap_or(0),
+                        frame_rate,
+                        pix_fmt: stream.pix_fmt.unwrap_or_default(),
+                        hdr,
+                    });
+                }
+                Some("audio") => {
+                    info.audio.push(AudioStream {
+                        codec: stream.codec_name.unwrap_or_default(),
+                        channels: stream.channels.unwrap_or(0),
+                        sample_rate: parse_number(stream.sample_rate.as_deref()) as u32,
+                        bitrate: parse_number(stream.bit_rate.as_deref()) as u64,
+                        language: stream
+                            .tags
+                            .and_then(|tags| tags.language)
+                            .unwrap_or_default(),
+                    });
+                }
+                _ => {}
+            }
+        }
+        Ok(info)
+    }
+
+    // a short label of the video stream, e.g. "hevc 1280x720 30fps"
+    pub fn summary(&self) -> String {
+        match &self.video {
+            Some(video) => format!(
+                "{} {}x{} {:.0}fps{}",
+                video.codec,
+                video.width,
+                video.height,
+                video.frame_rate,
+                if video.hdr { " HDR" } else { "" }
+            ),
+            None => "no video".to_string(),
+        }
+    }
+
+    // all the information, one item per line
+    pub fn details(&self) -> String {
+        let mut lines = vec![
+            format!("container: {}", self.container),
+            format!("duration: {:.2}s", self.duration),
+            format!("size: {} bytes", self.size),
+            format!("bitrate: {} kbits/s", self.bitrate / 1000),
+        ];
+        match &self.video {
+            Some(video) => {
+                lines.push(format!("video: {}", video.codec));
+                lines.push(format!("resolution: {}x{}", video.width, video.height));
+                lines.push(format!("frame rate: {:.3}", video.frame_rate));
+                lines.push(format!("pixel format: {}", video.pix_fmt));
+                lines.push(format!("hdr: {}", video.hdr));
+            }
+            None => lines.push("video: none".to_string()),
+        }
+        if self.audio.is_empty() {
+            lines.push("audio: none".to_string());
+        }
+        for (index, audio) in self.audio.iter().enumerate() {
+            lines.push(format!(
+                "audio #{}: {} {}ch {}Hz {} kbits/s {}",
+                index + 1,
+                audio.codec,
+                audio.channels,
+                audio.sample_rate,
+                audio.bitrate / 1000,
+                audio.language
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+// "123.45" -> 123.45, missing or "N/A" -> 0
+fn parse_number(value: Option<&str>) -> f64 {
+    value.and_then(|v| v.parse().ok()).unwrap_or(0.0)
+}
+
+// "30000/1001" -> 29.97
+fn parse_rate(value: Option<&str>) -> f64 {
+    let Some((num, den)) = value.and_then(|v| v.split_once('/')) else {
+        return parse_number(value);
+    };
+    let (num, den) = (parse_number(Some(num)), parse_number(Some(den)));
+    if den > 0.0 { num / den } else { 0.0 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cover_art_is_not_the_video() {
+        // the cover of an m4a comes before its audio, the mjpeg of a movie after the video
+        let info = MediaInfo::from_ffprobe(
+            r#"{"streams": [
+                {"codec_type": "video", "codec_name": "mjpeg", "width": 600, "height": 600,
+                 "disposition": {"default": 0, "attached_pic": 1}},
+                {"codec_type": "audio", "codec_name": "aac", "channels": 2,
+                 "disposition": {"default": 1, "attached_pic": 0}}
+            ]}"#,
+        )
+        .unwrap();
+        assert!(info.video.is_none());
+        assert_eq!(info.audio.len(), 1);
+
+        let info = MediaInfo::from_ffprobe(
+            r#"{"streams": [
+                {"codec_type": "video", "codec_name": "mjpeg", "width": 600, "height": 600,
+                 "disposition": {"attached_pic": 1}},
+                {"codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+                 "avg_frame_rate": "30/1", "disposition": {"attached_pic": 0}}
+            ]}"#,
+        )
+        .unwrap();
+        assert_eq!(info.summary(), "h264 1920x1080 30fps");
+    }
+}
@@ -1,9 +1,18 @@
-use crate::define::MenuImportButton;
+use crate::define::{MenuImportButton, QualityMetric, WatchFolder};
+use crate::utility::compare::{extract_frame, extract_pair};
+use crate::utility::watch::WatchedFile;
 
 use super::custom::*;
+use bevy::platform::collections::HashMap;
 use bevy::prelude::*;
+use image::RgbaImage;
+use std::collections::{BTreeSet, VecDeque};
+use std::path::PathBuf;
 use tokio::sync::{broadcast, mpsc};
 
+// (index, the measured output, its metric and score)
+pub type QualityResult = (usize, PathBuf, Result<(QualityMetric, f64), String>);
+
 #[derive(Debug, Resource)]
 pub struct ProcessState {
     pub progress_tx: mpsc::Sender<ProgressInfo>,
@@ -13,6 +22,43 @@ pub struct ProcessState {
     pub toast_message: Vec<String>,
     pub toast_tx: mpsc::Sender<String>,
     pub toast_rx: mpsc::Receiver<String>,
+    pub log_tx: mpsc::Sender<(usize, String)>, // ffmpeg stderr lines of each task
+    pub log_rx: mpsc::Receiver<(usize, String)>,
+    pub import_tx: mpsc::Sender<Vec<String>>, // files of a drop or of the file dialog
+    pub import_rx: mpsc::Receiver<Vec<String>>,
+    pub preview_tx: mpsc::Sender<PreviewImage>, // images made in a thread for the preview window
+    pub preview_rx: mpsc::Receiver<PreviewImage>,
+    pub quality_tx: mpsc::Sender<QualityResult>, // scores measured in a thread
+    pub quality_rx: mpsc::Receiver<QualityResult>,
+}
+
+impl ProcessState {
+    pub fn new(layout: Option<Entity>) -> Self {
+        let (progress_tx, progress_rx) = mpsc::channel::<ProgressInfo>(100);
+        let (toast_tx, toast_rx) = mpsc::channel::<String>(100);
+        let (main_tx, _) = broadcast::channel::<ProcessSignal>(100);
+        let (log_tx, log_rx) = mpsc::channel::<(usize, String)>(1000);
+        let (import_tx, import_rx) = mpsc::channel::<Vec<String>>(10);
+        let (preview_tx, preview_rx) = mpsc::channel::<PreviewImage>(4);
+        let (quality_tx, quality_rx) = mpsc::channel::<QualityResult>(100);
+        Self {
+            progress_tx,
+            progress_rx,
+            main_tx,
+            layout,
+            toast_message: Vec::new(),
+            toast_tx,
+            toast_rx,
+            log_tx,
+            log_rx,
+            import_tx,
+            import_rx,
+            preview_tx,
+            preview_rx,
+            quality_tx,
+            quality_rx,
+        }
+    }
 }
 
 #[derive(Debug, Resource, Default)]
@@ -25,8 +71,244 @@ pub struct PathDatas {
 #[derive(Debug, Resource)]
 pub struct ProcessMenu {
     pub import_type: MenuImportButton,
-    pub hide_done: bool,
     pub toggle_setting: bool,
+    pub log_index: Option<usize>,     // the line shown in the log window
+    pub details_index: Option<usize>, // the line shown in the details window
+    pub preset: String,               // the preset used by Start All when the rules chose nothing
+    pub contact_sheet: bool,          // the snapshot buttons make contact sheets
+}
+
+// the number of stderr lines kept for each task
+pub const TASK_LOG_CAPACITY: usize = 500;
+
+// ffmpeg output of each task (ring buffer)
+#[derive(Debug, Resource, Default)]
+pub struct TaskLogs(pub HashMap<usize, VecDeque<String>>);
+
+impl TaskLogs {
+    pub fn push(&mut self, index: usize, line: String) {
+        let lines = self.0.entry(index).or_default();
+        if lines.len() >= TASK_LOG_CAPACITY {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    pub fn remap(&mut self, mapping: &[Option<usize>]) {
+        remap(&mut self.0, mapping);
+    }
+}
+
+// a job waiting for a free slot in the task queue
+#[derive(Debug, Clone)]
+pub struct QueuedJob {
+    pub index: usize,   // index of the line in FilesState
+    pub preset: String, // name of the preset
+    pub resume: bool,   // go on after the partial output of an interrupted run
+}
+
+#[derive(Debug, Resource)]
+pub struct TaskQueue {
+    pub jobs: VecDeque<QueuedJob>,
+    pub max_parallel: usize, // how many ffmpeg processes can run at the same time
+    pub paused: bool,        // stop taking jobs from the queue (running tasks continue)
+}
+
+impl Default for TaskQueue {
+    fn default() -> Self {
+        Self {
+            jobs: VecDeque::new(),
+            max_parallel: 1,
+            paused: false,
+        }
+    }
+}
+
+impl TaskQueue {
+    // put a line into the queue, do nothing if it is already queued
+    pub fn push(&mut self, index: usize, preset: String) {
+        if self.jobs.iter().any(|job| job.index == index) {
+            return;
+        }
+        self.jobs.push_back(QueuedJob {
+            index,
+            preset,
+            resume: false,
+        });
+    }
+    // put an interrupted line into the queue, it encodes the rest of its partial output
+    pub fn resume(&mut self, index: usize, preset: String) {
+        self.push(index, preset);
+        if let Some(job) = self.jobs.back_mut().filter(|job| job.index == index) {
+            job.resume = true;
+        }
+    }
+    // take a line out of the queue
+    pub fn remove(&mut self, index: usize) {
+        self.jobs.retain(|job| job.index != index);
+    }
+    // follow the lines after some were removed from the list
+    pub fn remap(&mut self, mapping: &[Option<usize>]) {
+        self.jobs
+            .retain_mut(|job| match mapping.get(job.index).copied().flatten() {
+                Some(index) => {
+                    job.index = index;
+                    true
+                }
+                None => false,
+            });
+    }
+}
+
+// the channels of the watch folder thread
+#[derive(Debug, Resource)]
+pub struct WatchState {
+    pub folder_tx: mpsc::UnboundedSender<WatchFolder>, // start watching another folder
+    pub file_rx: mpsc::Receiver<WatchedFile>,          // the files that finished being written
+}
+
+// the image in the preview window and the cells of a grid
+#[derive(Debug, Default, Resource)]
+pub struct PreviewGrid {
+    pub image: Handle<Image>,
+    pub cells: Vec<Handle<Image>>,
+    pub columns: u32,
+    pub hovered: Option<usize>, // the cell shown instead of the whole grid
+}
+
+// the lines selected for the bulk actions
+#[derive(Debug, Default, Resource)]
+pub struct Selection {
+    pub lines: BTreeSet<usize>,
+    pub anchor: Option<usize>, // the last clicked line, where a shift-click range starts
+}
+
+// frames of both files at one timestamp, (timestamp, source frame, output frame)
+pub type FramePair = (f64, Result<(RgbaImage, RgbaImage), String>);
+
+// the state of the comparison window
+#[derive(Debug, Resource)]
+pub struct CompareView {
+    pub index: Option<usize>, // the line compared, None when the window is closed
+    pub source: String,
+    pub output: String,
+    pub duration: f64,   // of the output
+    pub secs: f64,       // the timestamp shown, in the output
+    pub offset: f64,     // where the output starts in the source (the in point of a trim)
+    pub frame_secs: f64, // the length of one frame
+    pub mode: CompareMode,
+    pub split: f32, // the position of the slider (0..1 of the frame width)
+    pub zoom: f32,
+    pub center: Vec2, // the center of the zoomed part (0..1 of the frame)
+    pub frames: Option<(RgbaImage, RgbaImage)>,
+    pub loading: bool,
+    pub dirty: bool, // the image needs to be composed again
+    pub image: Handle<Image>,
+    pub frame_tx: mpsc::Sender<FramePair>,
+    pub frame_rx: mpsc::Receiver<FramePair>,
+}
+
+impl Default for CompareView {
+    fn default() -> Self {
+        let (frame_tx, frame_rx) = mpsc::channel::<FramePair>(4);
+        Self {
+            index: None,
+            source: String::new(),
+            output: String::new(),
+            duration: 0.0,
+            secs: 0.0,
+            offset: 0.0,
+            frame_secs: 1.0 / 25.0,
+            mode: CompareMode::Split,
+            split: 0.5,
+            zoom: 1.0,
+            center: Vec2::splat(0.5),
+            frames: None,
+            loading: false,
+            dirty: false,
+            image: Handle::default(),
+            frame_tx,
+            frame_rx,
+        }
+    }
+}
+
+impl CompareView {
+    // move to another timestamp, the frames are extracted in a thread
+    pub fn seek(&mut self, secs: f64) {
+        self.secs = secs.clamp(0.0, (self.duration - self.frame_secs).max(0.0));
+        self.loading = true;
+        let (source, output, secs) = (self.source.clone(), self.output.clone(), self.secs);
+        let offset = self.offset;
+        let frame_tx = self.frame_tx.clone();
+        std::thread::spawn(move || {
+            let pair = extract_pair(&source, &output, secs, offset);
+            let _ = frame_tx.blocking_send((secs, pair));
+        });
+    }
+}
+
+// the height of the frames in the edit window
+const EDIT_FRAME_HEIGHT: u32 = 480;
+
+// (timestamp, frame) for the edit window
+pub type EditFrame = (f64, Result<RgbaImage, String>);
+
+// the state of the edit window, the edit itself is kept in FilesState::edits
+#[derive(Debug, Resource)]
+pub struct EditView {
+    pub index: Option<usize>, // the line edited, None when the window is closed
+    pub path: String,
+    pub duration: f64,
+    pub size: UVec2, // the size of the source frame, the crop is in its pixels
+    pub secs: f64,   // the scrub position
+    pub loading: bool,
+    pub pending: Option<f64>, // the position to load once the current frame arrives
+    pub drag: Option<(Vec2, Vec2)>, // the crop being drawn (0..1 of the frame)
+    pub image: Handle<Image>,
+    pub frame_tx: mpsc::Sender<EditFrame>,
+    pub frame_rx: mpsc::Receiver<EditFrame>,
+}
+
+impl Default for EditView {
+    fn default() -> Self {
+        let (frame_tx, frame_rx) = mpsc::channel::<EditFrame>(4);
+        Self {
+            index: None,
+            path: String::new(),
+            duration: 0.0,
+            size: UVec2::ZERO,
+            secs: 0.0,
+            loading: false,
+            pending: None,
+            drag: None,
+            image: Handle::default(),
+            frame_tx,
+            frame_rx,
+        }
+    }
+}
+
+impl EditView {
+    // move the scrub position, only one frame is extracted at a time
+    pub fn seek(&mut self, secs: f64) {
+        self.secs = secs.clamp(0.0, self.duration.max(0.0));
+        if self.loading {
+            self.pending = Some(self.secs);
+        } else {
+            self.load(self.secs);
+        }
+    }
+
+    pub fn load(&mut self, secs: f64) {
+        self.loading = true;
+        let path = self.path.clone();
+        let frame_tx = self.frame_tx.clone();
+        std::thread::spawn(move || {
+            let frame = extract_frame(&path, secs, Some(EDIT_FRAME_HEIGHT));
+            let _ = frame_tx.blocking_send((secs, frame));
+        });
+    }
 }
 
 #[derive(Debug, Resource)]
@@ -0,0 +1,186 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use super::custom::{FilesState, TaskStatus};
+use super::probe::MediaInfo;
+
+// saved next to files_state.json
+pub const RULES_FILE: &str = "rules.json";
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub struct RuleCondition {
+    pub codec: Option<String>, // video codec name reported by ffprobe, e.g. "hevc"
+    pub min_height: Option<u32>,
+    pub max_height: Option<u32>,
+    pub hdr: Option<bool>,
+}
+
+impl RuleCondition {
+    pub fn matches(&self, info: &MediaInfo) -> bool {
+        let Some(video) = &info.video else {
+            return false;
+        };
+        if let Some(codec) = &self.codec {
+            if !video.codec.eq_ignore_ascii_case(codec) {
+                return false;
+            }
+        }
+        if self.min_height.is_some_and(|h| video.height < h) {
+            return false;
+        }
+        if self.max_height.is_some_and(|h| video.height > h) {
+            return false;
+        }
+        if self.hdr.is_some_and(|hdr| video.hdr != hdr) {
+            return false;
+        }
+        true
+    }
+}
+
+impl std::fmt::Display for RuleCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts = vec![];
+        if let Some(codec) = &self.codec {
+            parts.push(format!("codec = {}", codec));
+        }
+        if let Some(h) = self.min_height {
+            parts.push(format!("height >= {}", h));
+        }
+        if let Some(h) = self.max_height {
+            parts.push(format!("height <= {}", h));
+        }
+        if let Some(hdr) = self.hdr {
+            parts.push(format!("hdr = {}", hdr));
+        }
+        if parts.is_empty() {
+            write!(f, "any")
+        } else {
+            write!(f, "{}", parts.join(" and "))
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RuleAction {
+    Skip,
+    Profile(String), // name of the preset
+}
+
+impl std::fmt::Display for RuleAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RuleAction::Skip => write!(f, "skip"),
+            RuleAction::Profile(name) => write!(f, "use {}", name),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProfileRule {
+    pub enabled: bool,
+    pub condition: RuleCondition,
+    pub action: RuleAction,
+}
+
+impl ProfileRule {
+    pub fn new(condition: RuleCondition, action: RuleAction) -> Self {
+        Self {
+            enabled: true,
+            condition,
+            action,
+        }
+    }
+}
+
+impl std::fmt::Display for ProfileRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "if {} then {}", self.condition, self.action)
+    }
+}
+
+// rules are checked in order, the first enabled rule that matches wins
+#[derive(Debug, Clone, Serialize, Deserialize, Resource)]
+pub struct ProfileRules {
+    pub rules: Vec<ProfileRule>,
+}
+
+impl Default for ProfileRules {
+    fn default() -> Self {
+        Self {
+            rules: vec![
+                ProfileRule::new(
+                    RuleCondition {
+                        codec: Some("hevc".to_string()),
+                        max_height: Some(720),
+                        ..default()
+                    },
+                    RuleAction::Skip,
+                ),
+                ProfileRule::new(
+                    RuleCondition {
+                        min_height: Some(1081),
+                        ..default()
+                    },
+                    RuleAction::Profile("hw".to_string()),
+                ),
+                ProfileRule::new(
+                    RuleCondition::default(),
+                    RuleAction::Profile("sf".to_string()),
+                ),
+            ],
+        }
+    }
+}
+
+impl ProfileRules {
+    pub fn load() -> Self {
+        let Ok(json) = std::fs::read_to_string(RULES_FILE) else {
+            return Self::default();
+        };
+        serde_json::from_str(&json).unwrap_or_else(|e| {
+            warn!("Failed to deserialize {}: {}", RULES_FILE, e);
+            Self::default()
+        })
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        std::fs::write(RULES_FILE, json).map_err(|e| e.to_string())
+    }
+
+    pub fn evaluate(&self, info: &MediaInfo) -> Option<&RuleAction> {
+        self.rules
+            .iter()
+            .find(|rule| rule.enabled && rule.condition.matches(info))
+            .map(|rule| &rule.action)
+    }
+
+    // choose the profile of a line, or mark it Skipped
+    // only lines that are not started yet are touched
+    pub fn apply(&self, index: usize, state: &mut FilesState) {
+        let Some(status) = state.status.get_mut(index) else {
+            return;
+        };
+        if !matches!(status, TaskStatus::Waiting | TaskStatus::Skipped) {
+            return;
+        }
+        let Some(info) = state.media.get(&index) else {
+            return;
+        };
+        match self.evaluate(info) {
+            Some(RuleAction::Skip) => {
+                *status = TaskStatus::Skipped;
+                state.profiles.remove(&index);
+            }
+            Some(RuleAction::Profile(name)) => {
+                *status = TaskStatus::Waiting;
+                state.profiles.insert(index, name.clone());
+            }
+            None => {
+                *status = TaskStatus::Waiting;
+                state.profiles.remove(&index);
+            }
+        }
+    }
+}
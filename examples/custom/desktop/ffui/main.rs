@@ -1,21 +1,41 @@
 use bevy::{
+    app::ScheduleRunnerPlugin,
     input_focus::{InputDispatchPlugin, tab_navigation::TabNavigationPlugin},
     prelude::*,
 };
 use once_cell::sync::Lazy;
+use std::time::Duration;
 use tokio::runtime::Runtime;
 
 mod define;
 mod systems;
+#[cfg(test)]
+mod testing;
 mod ui;
 mod utility;
 
 use crate::define::*;
+use crate::utility::cli::{self, CliOptions};
 
 static TOKIO_RT: Lazy<Runtime> = Lazy::new(|| Runtime::new().unwrap());
 const FONT_BYTES: &[u8] = include_bytes!("../../../assets/fonts/SourceHanSansCN-Normal.otf");
 
-fn main() {
+fn main() -> AppExit {
+    match cli::parse(std::env::args().skip(1)) {
+        Ok(Some(options)) => {
+            if let Err(e) = cli::check_preset(&options, &FfmpegArg::load()) {
+                eprintln!("{}\n\n{}", e, cli::USAGE);
+                return AppExit::from_code(2);
+            }
+            return headless(options);
+        }
+        Ok(None) => {}
+        Err(e) => {
+            eprintln!("{}\n\n{}", e, cli::USAGE);
+            return AppExit::from_code(2);
+        }
+    }
+
     App::new()
         .add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
@@ -28,37 +48,137 @@ fn main() {
         .init_state::<AppState>()
         .enable_state_scoped_entities::<AppState>()
         .init_resource::<PathDatas>()
-        .add_systems(Startup, ui::setup::setup)
+        .init_resource::<ListFilter>()
+        .init_resource::<Selection>()
+        .init_resource::<PreviewGrid>()
+        .init_resource::<CompareView>()
+        .init_resource::<EditView>()
+        .add_event::<ProgressChanged>()
+        .add_systems(
+            Startup,
+            (ui::setup::setup, systems::restore_session).chain(),
+        )
         .add_systems(OnEnter(AppState::Monitor), ui::enter_monitor)
         .add_systems(OnEnter(AppState::Setting), ui::enter_setting)
-        .add_systems(Update, ui::focus_system.run_if(in_state(AppState::Setting)))
+        .add_systems(OnExit(AppState::Setting), systems::exit_setting)
+        .add_systems(
+            Update,
+            (
+                ui::focus_system,
+                ui::refresh_setting,
+                systems::rule_toggle_interaction,
+                systems::text_input_system,
+                systems::arg_submit_interaction,
+                systems::arg_row_interaction,
+            )
+                .run_if(in_state(AppState::Setting)),
+        )
+        .add_systems(
+            Update,
+            (
+                ui::focus_system,
+                systems::search_input_system,
+                systems::select_interaction,
+                systems::select_all_shortcut,
+                systems::selection_update,
+                systems::bulk_interaction,
+                systems::preview_receiver,
+                systems::preview_scrub,
+                // the comparison window
+                (
+                    systems::compare_interaction,
+                    systems::compare_control_interaction,
+                    systems::compare_keys,
+                    systems::compare_pointer,
+                    systems::compare_receiver,
+                    systems::compare_window_update,
+                )
+                    .chain(),
+                // the edit window
+                (
+                    systems::edit_interaction,
+                    systems::edit_control_interaction,
+                    systems::edit_keys,
+                    systems::edit_timeline,
+                    systems::edit_crop_drag,
+                    systems::edit_receiver,
+                    systems::edit_window_update,
+                )
+                    .chain(),
+            )
+                .run_if(in_state(AppState::Monitor)),
+        )
         .add_systems(
             Update,
             (
                 systems::task_interaction,
+                systems::task_scheduler,
                 systems::replace_interaction,
+                systems::undo_replace_interaction,
                 systems::snapshot_interaction,
                 systems::opendir_interaction,
+                systems::reveal_interaction,
+                systems::copy_path_interaction,
                 systems::menu_interaction,
                 systems::update_task_button_text,
                 systems::toast_animate,
                 systems::toast_consumer,
                 systems::toast_receiver,
                 systems::move_or_resize_windows,
-                systems::shortcuts,
+                // the ways files are imported
+                (
+                    systems::shortcuts,
+                    systems::watch_receiver,
+                    systems::file_drop,
+                    systems::import_receiver,
+                ),
+                systems::log_receiver,
+                systems::log_interaction,
+                systems::details_interaction,
             ),
         )
         .add_systems(
             Update,
             (
                 ui::refresh_lines,
+                systems::progress_receiver,
+                systems::quality_trigger,
+                systems::quality_receiver,
                 ui::progress_bar_update,
+                systems::autosave_session,
                 ui::setup::on_window_close,
                 ui::setup::update_scroll_position,
-                ui::show_hide_row,
                 ui::show_import_type,
+                ui::update_error_text,
+                ui::log_window_update,
+                ui::update_media_text,
+                ui::update_quality_text,
+                ui::details_window_update,
+            )
+                .chain(),
+        )
+        .run()
+}
+
+// the transcode pipeline without a window, for cron and scripts
+fn headless(options: CliOptions) -> AppExit {
+    App::new()
+        .add_plugins(MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_millis(50))))
+        .insert_resource(options)
+        .add_event::<ProgressChanged>()
+        .add_systems(Startup, systems::headless_setup)
+        .add_systems(
+            Update,
+            (
+                systems::task_scheduler,
+                systems::progress_receiver,
+                systems::headless_replace,
+                systems::headless_report,
+                systems::log_receiver,
+                systems::headless_messages,
+                systems::headless_exit,
             )
                 .chain(),
         )
-        .run();
+        .run()
 }
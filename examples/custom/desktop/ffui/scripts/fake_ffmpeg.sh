@@ -0,0 +1,32 @@
+#!/bin/sh
+# a stand-in for ffmpeg to check the hardware detection of ffui
+#
+#   FAKE_HWACCELS="cuda vaapi" FAKE_ENCODERS="hevc_nvenc" \
+#   FFUI_FFMPEG=$PWD/scripts/fake_ffmpeg.sh <ffui binary>
+#
+# FAKE_HWACCELS and FAKE_ENCODERS are space separated, empty means none
+case "$2" in
+-hwaccels)
+    echo "Hardware acceleration methods:"
+    for name in $FAKE_HWACCELS; do
+        echo "$name"
+    done
+    echo
+    ;;
+-encoders)
+    echo "Encoders:"
+    echo " V..... = Video"
+    echo " A..... = Audio"
+    echo " ------"
+    echo " V....D libx264              libx264 H.264 / AVC / MPEG-4 AVC (codec h264)"
+    echo " V....D libx265              libx265 H.265 / HEVC (codec hevc)"
+    for name in $FAKE_ENCODERS; do
+        echo " V....D $name              fake hardware encoder (codec hevc)"
+    done
+    echo " A....D aac                  AAC (Advanced Audio Coding)"
+    ;;
+*)
+    echo "fake_ffmpeg.sh only answers -hwaccels and -encoders" >&2
+    exit 1
+    ;;
+esac
@@ -0,0 +1,336 @@
+use crate::define::*;
+use crate::systems::analyze_media;
+use crate::utility::replace::replace_line;
+use crate::utility::sheet::tile_images;
+use crate::utility::task::snapshot;
+use bevy::prelude::*;
+
+// the snapshot grid shows at most this many lines
+const SNAPSHOT_GRID_MAX: usize = 16;
+const SNAPSHOT_GRID_COLUMNS: u32 = 4;
+
+// click selects a line, shift-click a range of the shown lines, ctrl-click toggles a line
+pub fn select_interaction(
+    interaction_query: Query<
+        (&Interaction, &IndexOfline),
+        (Changed<Interaction>, With<SelectButton>),
+    >,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut selection: ResMut<Selection>,
+    data: Res<PathDatas>,
+    filter: Res<ListFilter>,
+) {
+    for (interaction, idx) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let index = idx.0;
+        let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+        let toggle = keyboard.any_pressed([
+            KeyCode::SuperLeft,
+            KeyCode::SuperRight,
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+        ]);
+        match selection.anchor {
+            Some(anchor) if shift => {
+                // the range follows the order of the list on screen
+                let shown = filter.apply(&data.state);
+                let (Some(from), Some(to)) = (
+                    shown.iter().position(|i| *i == anchor),
+                    shown.iter().position(|i| *i == index),
+                ) else {
+                    continue;
+                };
+                let range = &shown[from.min(to)..=from.max(to)];
+                if !toggle {
+                    selection.lines.clear();
+                }
+                selection.lines.extend(range.iter().copied());
+            }
+            _ if toggle => {
+                if !selection.lines.remove(&index) {
+                    selection.lines.insert(index);
+                }
+                selection.anchor = Some(index);
+            }
+            _ => {
+                // a click on the only selected line clears the selection
+                let only = selection.lines.len() == 1 && selection.lines.contains(&index);
+                selection.lines.clear();
+                if !only {
+                    selection.lines.insert(index);
+                }
+                selection.anchor = Some(index);
+            }
+        }
+    }
+}
+
+// mark the selected rows and show the size of the selection
+pub fn selection_update(
+    mut selection: ResMut<Selection>,
+    data: Res<PathDatas>,
+    mut cell_query: Query<(&IndexOfline, &Interaction, &mut BackgroundColor), With<SelectButton>>,
+    mut text: Single<&mut Text, With<SelectionText>>,
+) {
+    // the lines may be gone after an import or a load
+    let len = data.state.lines.len();
+    if selection.lines.iter().any(|index| *index >= len) {
+        selection.lines.retain(|index| *index < len);
+    }
+    for (idx, interaction, mut bg) in cell_query.iter_mut() {
+        let color = if selection.lines.contains(&idx.0) {
+            Color::srgb_u8(40, 80, 160)
+        } else if *interaction == Interaction::Hovered {
+            Color::srgb_u8(0, 84, 0)
+        } else {
+            Color::WHITE.with_alpha(0.02)
+        };
+        if bg.0 != color {
+            bg.0 = color;
+        }
+    }
+    if selection.is_changed() {
+        text.0 = format!("{} selected", selection.lines.len());
+    }
+}
+
+// the buttons of the selection bar
+pub fn bulk_interaction(
+    interaction_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut selection: ResMut<Selection>,
+    mut data: ResMut<PathDatas>,
+    mut queue: ResMut<TaskQueue>,
+    mut logs: ResMut<TaskLogs>,
+    mut process_menu: ResMut<ProcessMenu>,
+    mut process_state: ResMut<ProcessState>,
+    mut compare: ResMut<CompareView>,
+    mut edit_view: ResMut<EditView>,
+    config: Res<AppConfig>,
+    ffmpeg_arg: Res<FfmpegArg>,
+) {
+    for (interaction, menu_button) in interaction_query.iter() {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        let Some(MenuBulkButton(action)) = menu_button
+            .button_type
+            .as_ref()
+            .as_any()
+            .downcast_ref::<MenuBulkButton>()
+        else {
+            continue;
+        };
+        if selection.lines.is_empty() {
+            process_state
+                .toast_message
+                .push("No line selected".to_string());
+            continue;
+        }
+        let lines: Vec<usize> = selection.lines.iter().copied().collect();
+        let message = match action {
+            BulkAction::Start => {
+                let mut count = 0;
+                for index in lines {
+                    if !matches!(
+                        data.state.status.get(index),
+                        Some(
+                            TaskStatus::Waiting
+                                | TaskStatus::Failed
+                                | TaskStatus::Cancelled
+                                | TaskStatus::Skipped
+                                | TaskStatus::Interrupted
+                        )
+                    ) {
+                        continue;
+                    }
+                    // the preset chosen by the rules, or the one picked in the menu
+                    let preset = data
+                        .state
+                        .profiles
+                        .get(&index)
+                        .cloned()
+                        .unwrap_or_else(|| process_menu.preset.clone());
+                    data.state.status[index] = TaskStatus::Queued;
+                    queue.push(index, preset);
+                    count += 1;
+                }
+                format!("{} tasks queued", count)
+            }
+            BulkAction::Cancel => {
+                let mut count = 0;
+                for index in lines {
+                    match data.state.status.get(index) {
+                        Some(TaskStatus::Queued) => {
+                            data.state.status[index] = TaskStatus::Waiting;
+                            queue.remove(index);
+                        }
+                        // the task thread reports back with Cancelled
+                        Some(TaskStatus::Running) => {
+                            let _ = process_state
+                                .main_tx
+                                .send(ProcessSignal::TaskInterrupt(index));
+                        }
+                        _ => continue,
+                    }
+                    count += 1;
+                }
+                format!("{} tasks cancelled", count)
+            }
+            BulkAction::Replace => {
+                let mut count = 0;
+                for index in lines {
+                    if data.state.status.get(index) != Some(&TaskStatus::Done) {
+                        continue;
+                    }
+                    if let Err(e) = data.state.quality_check(index, &config.quality) {
+                        process_state.toast_message.push(format!(
+                            "Replace of line {} blocked: {}",
+                            index + 1,
+                            e
+                        ));
+                        continue;
+                    }
+                    match replace_line(&mut data.state, index, &config) {
+                        Ok(warnings) => {
+                            process_state.toast_message.extend(warnings);
+                            count += 1;
+                        }
+                        Err(e) => process_state.toast_message.push(format!(
+                            "Replace of line {} failed: {}",
+                            index + 1,
+                            e
+                        )),
+                    }
+                }
+                format!("{} files replaced", count)
+            }
+            BulkAction::Remove => {
+                // a running task reports with the index it was started with, the results
+                // of a pending analysis follow their path to the new index
+                let running = data
+                    .state
+                    .status
+                    .iter()
+                    .any(|status| matches!(status, TaskStatus::Running));
+                if running {
+                    "Can not remove lines while tasks are running".to_string()
+                } else {
+                    let mapping = data.state.remove_lines(&selection.lines);
+                    queue.remap(&mapping);
+                    logs.remap(&mapping);
+                    let follow = |index: Option<usize>| mapping.get(index?).copied().flatten();
+                    process_menu.log_index = follow(process_menu.log_index);
+                    process_menu.details_index = follow(process_menu.details_index);
+                    compare.index = follow(compare.index);
+                    edit_view.index = follow(edit_view.index);
+                    selection.lines.clear();
+                    selection.anchor = None;
+                    data.changed = true;
+                    format!("{} lines removed", lines.len())
+                }
+            }
+            BulkAction::Snapshot => {
+                let files: Vec<(String, u64)> = lines
+                    .iter()
+                    .take(SNAPSHOT_GRID_MAX)
+                    .filter_map(|index| {
+                        let total_secs = data.state.progress.get(index).map_or(0, |p| p.total);
+                        Some((data.state.lines.get(*index)?.clone(), total_secs))
+                    })
+                    .collect();
+                let args = ffmpeg_arg.snapshot.clone();
+                let preview_tx = process_state.preview_tx.clone();
+                let toast_tx = process_state.toast_tx.clone();
+                std::thread::spawn(move || {
+                    let frames: Vec<_> = files
+                        .into_iter()
+                        .filter_map(|(path, total_secs)| {
+                            let png = snapshot(path, total_secs, args.clone()).ok()?;
+                            image::load_from_memory(&png).ok()
+                        })
+                        .collect();
+                    if frames.is_empty() {
+                        let _ = toast_tx.blocking_send("No snapshot was taken".to_string());
+                        return;
+                    }
+                    let preview = PreviewImage {
+                        image: tile_images(&frames, SNAPSHOT_GRID_COLUMNS, 320),
+                        columns: SNAPSHOT_GRID_COLUMNS,
+                        cells: frames.len(),
+                    };
+                    let _ = preview_tx.blocking_send(preview);
+                });
+                format!(
+                    "Taking snapshots of {} files",
+                    lines.len().min(SNAPSHOT_GRID_MAX)
+                )
+            }
+            BulkAction::Analyze => {
+                let files: Vec<(usize, String)> = lines
+                    .into_iter()
+                    .filter_map(|index| Some((index, data.state.lines.get(index)?.clone())))
+                    .collect();
+                let count = files.len();
+                analyze_media(
+                    files,
+                    process_state.progress_tx.clone(),
+                    ffmpeg_arg.analyze.clone(),
+                    process_state.toast_tx.clone(),
+                );
+                format!("Analyzing {} files", count)
+            }
+            BulkAction::Retry => {
+                let mut count = 0;
+                for index in lines {
+                    if !matches!(
+                        data.state.status.get(index),
+                        Some(TaskStatus::Failed | TaskStatus::Cancelled | TaskStatus::Interrupted)
+                    ) {
+                        continue;
+                    }
+                    // the preset chosen by the rules, or the one picked in the menu
+                    let preset = data
+                        .state
+                        .profiles
+                        .get(&index)
+                        .cloned()
+                        .unwrap_or_else(|| process_menu.preset.clone());
+                    data.state.status[index] = TaskStatus::Queued;
+                    queue.push(index, preset);
+                    count += 1;
+                }
+                format!("{} failed tasks queued again", count)
+            }
+        };
+        process_state.toast_message.push(message);
+    }
+}
+
+// Cmd+A selects the shown lines, Escape clears the selection (or closes the compare or edit window)
+pub fn select_all_shortcut(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut selection: ResMut<Selection>,
+    data: Res<PathDatas>,
+    filter: Res<ListFilter>,
+    compare: Res<CompareView>,
+    edit: Res<EditView>,
+) {
+    let modifier = keyboard.any_pressed([
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+    ]);
+    if modifier && keyboard.just_pressed(KeyCode::KeyA) {
+        selection.lines = filter.apply(&data.state).into_iter().collect();
+    } else if keyboard.just_pressed(KeyCode::Escape)
+        && compare.index.is_none()
+        && edit.index.is_none()
+        && !selection.lines.is_empty()
+    {
+        selection.lines.clear();
+        selection.anchor = None;
+    }
+}
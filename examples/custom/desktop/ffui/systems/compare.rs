@@ -0,0 +1,276 @@
+use crate::define::*;
+use crate::utility::compare::{compose, view_rect};
+use bevy::image::ImageSampler;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use std::path::PathBuf;
+
+const ZOOM_MAX: f32 = 16.0;
+const JUMP_SECS: f64 = 10.0;
+
+// (source, output) of a finished line, a replaced source is compared from the trash
+fn compare_paths(state: &FilesState, index: usize) -> Option<(PathBuf, PathBuf)> {
+    match state.status.get(index)? {
+        TaskStatus::Done => Some((
+            PathBuf::from(state.lines.get(index)?),
+            state.outputs.get(&index)?.clone(),
+        )),
+        TaskStatus::Replaced => {
+            let record = state.replaced.get(&index)?;
+            Some((record.trash.clone(), record.target.clone()))
+        }
+        _ => None,
+    }
+}
+
+// compare button interaction, opens the middle of the file
+pub fn compare_interaction(
+    mut interaction_query: Query<
+        (&Interaction, &IndexOfline, &mut BackgroundColor),
+        (Changed<Interaction>, With<CompareButton>),
+    >,
+    data: Res<PathDatas>,
+    mut view: ResMut<CompareView>,
+    mut edit: ResMut<EditView>,
+    mut process_state: ResMut<ProcessState>,
+) {
+    for (interaction, idx, mut bg) in interaction_query.iter_mut() {
+        let paths = compare_paths(&data.state, idx.0);
+        match *interaction {
+            Interaction::Hovered if paths.is_some() => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                if view.index == Some(idx.0) {
+                    view.index = None;
+                    continue;
+                }
+                let Some((source, output)) = paths else {
+                    process_state
+                        .toast_message
+                        .push("compare: the line has no output yet".to_string());
+                    continue;
+                };
+                // a cropped frame can not be laid over the source, the parts of a segmented
+                // output each start at 0
+                let edit = data.state.edits.get(&idx.0).cloned().unwrap_or_default();
+                if edit.crop.is_some() || edit.segment.is_some() {
+                    process_state.toast_message.push(
+                        "compare: cropped or segmented outputs can not be compared".to_string(),
+                    );
+                    continue;
+                }
+                if !source.exists() || !output.exists() {
+                    process_state
+                        .toast_message
+                        .push("compare: the source or the output is missing".to_string());
+                    continue;
+                }
+                let media = data.state.media.get(&idx.0);
+                // the total of a trimmed line is already the length of the output
+                let duration = media.map_or_else(
+                    || data.state.progress.get(&idx.0).map_or(0, |p| p.total) as f64,
+                    |info| edit.duration(info.duration),
+                );
+                let frame_rate = media
+                    .and_then(|info| info.video.as_ref())
+                    .map(|video| video.frame_rate)
+                    .filter(|rate| *rate > 0.0)
+                    .unwrap_or(25.0);
+                // the arrows of both windows would move at the same time
+                edit.index = None;
+                view.index = Some(idx.0);
+                view.source = source.to_string_lossy().to_string();
+                view.output = output.to_string_lossy().to_string();
+                view.duration = duration;
+                view.offset = edit.start.unwrap_or(0.0);
+                view.frame_secs = 1.0 / frame_rate;
+                view.zoom = 1.0;
+                view.center = Vec2::splat(0.5);
+                view.split = 0.5;
+                view.frames = None;
+                view.seek(duration / 2.0);
+            }
+            _ => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 0, 0));
+            }
+        }
+    }
+}
+
+// toolbar of the comparison window
+pub fn compare_control_interaction(
+    mut interaction_query: Query<
+        (&Interaction, &CompareControl, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut view: ResMut<CompareView>,
+) {
+    for (interaction, control, mut bg) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                let secs = view.secs;
+                let frame_secs = view.frame_secs;
+                match control.0 {
+                    CompareAction::PrevFrame => view.seek(secs - frame_secs),
+                    CompareAction::NextFrame => view.seek(secs + frame_secs),
+                    CompareAction::Back => view.seek(secs - JUMP_SECS),
+                    CompareAction::Forward => view.seek(secs + JUMP_SECS),
+                    CompareAction::Mode => {
+                        view.mode = match view.mode {
+                            CompareMode::Split => CompareMode::SideBySide,
+                            CompareMode::SideBySide => CompareMode::Split,
+                        };
+                        view.dirty = true;
+                    }
+                    CompareAction::ZoomIn => {
+                        view.zoom = (view.zoom * 2.0).min(ZOOM_MAX);
+                        view.dirty = true;
+                    }
+                    CompareAction::ZoomOut => {
+                        view.zoom = (view.zoom / 2.0).max(1.0);
+                        view.dirty = true;
+                    }
+                    CompareAction::Close => view.index = None,
+                }
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 0, 0));
+            }
+        }
+    }
+}
+
+// arrows step frames (shift: 10 seconds), +/- zoom, escape closes
+pub fn compare_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    focus: Res<InputFocus>,
+    mut view: ResMut<CompareView>,
+) {
+    if view.index.is_none() || focus.0.is_some() {
+        return;
+    }
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let step = if shift { JUMP_SECS } else { view.frame_secs };
+    let secs = view.secs;
+    if keyboard.just_pressed(KeyCode::ArrowLeft) {
+        view.seek(secs - step);
+    } else if keyboard.just_pressed(KeyCode::ArrowRight) {
+        view.seek(secs + step);
+    } else if keyboard.just_pressed(KeyCode::Equal) {
+        view.zoom = (view.zoom * 2.0).min(ZOOM_MAX);
+        view.dirty = true;
+    } else if keyboard.just_pressed(KeyCode::Minus) {
+        view.zoom = (view.zoom / 2.0).max(1.0);
+        view.dirty = true;
+    } else if keyboard.just_pressed(KeyCode::Escape) {
+        view.index = None;
+    }
+}
+
+// drag the image to move the split, right-drag to move the zoomed part
+pub fn compare_pointer(
+    image_query: Single<&RelativeCursorPosition, With<CompareImage>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut view: ResMut<CompareView>,
+) {
+    let Some((a, _)) = view.frames.as_ref().filter(|_| view.index.is_some()) else {
+        return;
+    };
+    let (width, height) = a.dimensions();
+    let Some(position) = image_query.normalized.filter(|_| image_query.cursor_over()) else {
+        return;
+    };
+    // the position is relative to the center of the node
+    let (mut x, y) = (position.x + 0.5, position.y + 0.5);
+    if view.mode == CompareMode::SideBySide {
+        // both halves show the same part of the frame
+        x = (x * 2.0).fract();
+    }
+    let (left, top, w, h) = view_rect(width, height, view.zoom, view.center);
+    let frame_x = (left as f32 + x * w as f32) / width as f32;
+    let frame_y = (top as f32 + y * h as f32) / height as f32;
+    if mouse.pressed(MouseButton::Left) && view.mode == CompareMode::Split {
+        view.split = frame_x.clamp(0.0, 1.0);
+        view.dirty = true;
+    } else if mouse.just_pressed(MouseButton::Right) && view.zoom > 1.0 {
+        view.center = Vec2::new(frame_x, frame_y).clamp(Vec2::ZERO, Vec2::ONE);
+        view.dirty = true;
+    }
+}
+
+// frames extracted by the thread of CompareView::seek
+pub fn compare_receiver(mut view: ResMut<CompareView>, mut process_state: ResMut<ProcessState>) {
+    while let Ok((secs, frames)) = view.frame_rx.try_recv() {
+        // an older seek finished after a newer one
+        if secs != view.secs {
+            continue;
+        }
+        view.loading = false;
+        match frames {
+            Ok(frames) => {
+                view.frames = Some(frames);
+                view.dirty = true;
+            }
+            Err(e) => process_state.toast_message.push(format!("compare: {}", e)),
+        }
+    }
+}
+
+// show or hide the window and compose the frames after a change
+pub fn compare_window_update(
+    window_query: Single<&mut Visibility, With<CompareWindow>>,
+    image_query: Single<(&mut ImageNode, &mut Node), With<CompareImage>>,
+    mut text_query: Single<&mut Text, With<CompareText>>,
+    mut images: ResMut<Assets<Image>>,
+    mut view: ResMut<CompareView>,
+) {
+    let mut visibility = window_query.into_inner();
+    let shown = if view.index.is_some() {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != shown {
+        *visibility = shown;
+    }
+    if view.index.is_none() || !view.is_changed() {
+        return;
+    }
+
+    let label = format!(
+        "{:.3}s / {:.1}s  zoom x{}{}",
+        view.secs,
+        view.duration,
+        view.zoom,
+        if view.loading { "  loading..." } else { "" }
+    );
+    if text_query.0 != label {
+        text_query.0 = label;
+    }
+
+    if !view.dirty {
+        return;
+    }
+    view.dirty = false;
+    let Some((a, b)) = view.frames.as_ref() else {
+        return;
+    };
+    let composed = compose(a, b, view.mode, view.split, view.zoom, view.center);
+    let (width, height) = composed.dimensions();
+    let usage = bevy::render::render_asset::RenderAssetUsages::default();
+    let mut image = Image::from_dynamic(image::DynamicImage::ImageRgba8(composed), true, usage);
+    // keep the pixels sharp when zoomed in
+    image.sampler = ImageSampler::nearest();
+    images.remove(&view.image);
+    let handle = images.add(image);
+    view.image = handle.clone();
+
+    let (mut image_node, mut node) = image_query.into_inner();
+    image_node.image = handle;
+    node.aspect_ratio = Some(width as f32 / height as f32);
+}
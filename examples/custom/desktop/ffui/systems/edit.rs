@@ -0,0 +1,343 @@
+use crate::define::*;
+use crate::utility::time::format_duration;
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+
+// the lengths the segments button cycles through, in seconds
+const SEGMENT_LENGTHS: [u32; 3] = [60, 300, 600];
+// a smaller drag is a click, the crop stays as it is
+const MIN_CROP: u32 = 16;
+
+// change the edit of a line, an edit without cuts is removed
+fn update_edit(state: &mut FilesState, index: usize, change: impl FnOnce(&mut FileEdit)) {
+    let edit = state.edits.entry(index).or_default();
+    change(edit);
+    if edit.is_empty() {
+        state.edits.remove(&index);
+    }
+}
+
+// hh:mm:ss.cc
+fn timestamp(secs: f64) -> String {
+    format!(
+        "{}.{:02}",
+        format_duration(secs as u64),
+        (secs.fract() * 100.0) as u32
+    )
+}
+
+// the cursor position relative to the node (0..1), None when the cursor is outside
+fn cursor_position(cursor: &RelativeCursorPosition) -> Option<Vec2> {
+    // the position is relative to the center of the node
+    cursor
+        .normalized
+        .filter(|_| cursor.cursor_over())
+        .map(|position| (position + Vec2::splat(0.5)).clamp(Vec2::ZERO, Vec2::ONE))
+}
+
+// the crop in pixels of the source, even sizes for the chroma subsampling of the encoders
+fn crop_rect(from: Vec2, to: Vec2, size: UVec2) -> CropRect {
+    let min = from.min(to) * size.as_vec2();
+    let max = from.max(to) * size.as_vec2();
+    let even = |value: f32| (value as u32) & !1;
+    CropRect {
+        x: even(min.x),
+        y: even(min.y),
+        width: even(max.x - min.x),
+        height: even(max.y - min.y),
+    }
+}
+
+// edit button interaction, the window needs the analyzed duration and frame size
+pub fn edit_interaction(
+    mut interaction_query: Query<
+        (&Interaction, &IndexOfline, &mut BackgroundColor),
+        (Changed<Interaction>, With<EditButton>),
+    >,
+    data: Res<PathDatas>,
+    mut view: ResMut<EditView>,
+    mut compare: ResMut<CompareView>,
+    mut process_state: ResMut<ProcessState>,
+) {
+    for (interaction, idx, mut bg) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                if view.index == Some(idx.0) {
+                    view.index = None;
+                    continue;
+                }
+                let (Some(path), Some(media)) =
+                    (data.state.lines.get(idx.0), data.state.media.get(&idx.0))
+                else {
+                    process_state
+                        .toast_message
+                        .push("edit: analyze the file first".to_string());
+                    continue;
+                };
+                let Some(video) = media.video.as_ref().filter(|_| media.duration > 0.0) else {
+                    process_state
+                        .toast_message
+                        .push("edit: the file has no video".to_string());
+                    continue;
+                };
+                // the arrows of both windows would move at the same time
+                compare.index = None;
+                view.index = Some(idx.0);
+                view.path = path.clone();
+                view.duration = media.duration;
+                view.size = UVec2::new(video.width, video.height);
+                view.drag = None;
+                let start = data
+                    .state
+                    .edits
+                    .get(&idx.0)
+                    .and_then(|edit| edit.start)
+                    .unwrap_or(0.0);
+                view.seek(start);
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 0, 0));
+            }
+        }
+    }
+}
+
+fn apply_action(action: EditAction, view: &mut EditView, state: &mut FilesState) {
+    let Some(index) = view.index else {
+        return;
+    };
+    let secs = view.secs;
+    match action {
+        EditAction::Back => view.seek(secs - 1.0),
+        EditAction::Forward => view.seek(secs + 1.0),
+        EditAction::SetIn => update_edit(state, index, |edit| {
+            edit.start = (secs > 0.0).then_some(secs);
+            if edit.end.is_some_and(|end| end <= secs) {
+                edit.end = None;
+            }
+        }),
+        EditAction::SetOut => update_edit(state, index, |edit| {
+            edit.end = (secs < view.duration).then_some(secs);
+            if edit.start.is_some_and(|start| start >= secs) {
+                edit.start = None;
+            }
+        }),
+        EditAction::ClearTrim => update_edit(state, index, |edit| {
+            edit.start = None;
+            edit.end = None;
+        }),
+        EditAction::ClearCrop => update_edit(state, index, |edit| edit.crop = None),
+        EditAction::Segment => update_edit(state, index, |edit| {
+            // off, then each length, then off again
+            edit.segment = match edit.segment {
+                None => Some(SEGMENT_LENGTHS[0]),
+                Some(length) => SEGMENT_LENGTHS.iter().copied().find(|next| *next > length),
+            };
+        }),
+        EditAction::Close => view.index = None,
+    }
+}
+
+// toolbar of the edit window
+pub fn edit_control_interaction(
+    mut interaction_query: Query<
+        (&Interaction, &EditControl, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut view: ResMut<EditView>,
+    mut data: ResMut<PathDatas>,
+) {
+    for (interaction, control, mut bg) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => apply_action(control.0, &mut view, &mut data.state),
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 0, 0));
+            }
+        }
+    }
+}
+
+// arrows move one second (shift: 10 seconds), I and O set the in and out points, escape closes
+pub fn edit_keys(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    focus: Res<InputFocus>,
+    mut view: ResMut<EditView>,
+    mut data: ResMut<PathDatas>,
+) {
+    if view.index.is_none() || focus.0.is_some() {
+        return;
+    }
+    let step = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        10.0
+    } else {
+        1.0
+    };
+    let secs = view.secs;
+    if keyboard.just_pressed(KeyCode::ArrowLeft) {
+        view.seek(secs - step);
+    } else if keyboard.just_pressed(KeyCode::ArrowRight) {
+        view.seek(secs + step);
+    } else if keyboard.just_pressed(KeyCode::KeyI) {
+        apply_action(EditAction::SetIn, &mut view, &mut data.state);
+    } else if keyboard.just_pressed(KeyCode::KeyO) {
+        apply_action(EditAction::SetOut, &mut view, &mut data.state);
+    } else if keyboard.just_pressed(KeyCode::Escape) {
+        view.index = None;
+    }
+}
+
+// click or drag on the timeline to scrub
+pub fn edit_timeline(
+    timeline_query: Single<&RelativeCursorPosition, With<EditTimeline>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut view: ResMut<EditView>,
+) {
+    if view.index.is_none() || !mouse.pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(position) = cursor_position(&timeline_query) else {
+        return;
+    };
+    let secs = position.x as f64 * view.duration;
+    // the same frame
+    if (secs - view.secs).abs() > 0.04 {
+        view.seek(secs);
+    }
+}
+
+// drag on the frame to draw the crop, it is stored when the button is released
+pub fn edit_crop_drag(
+    image_query: Single<&RelativeCursorPosition, With<EditImage>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut view: ResMut<EditView>,
+    mut data: ResMut<PathDatas>,
+) {
+    let Some(index) = view.index else {
+        return;
+    };
+    let position = cursor_position(&image_query);
+    if mouse.just_pressed(MouseButton::Left) {
+        view.drag = position.map(|position| (position, position));
+    } else if mouse.pressed(MouseButton::Left)
+        && let (Some((from, _)), Some(position)) = (view.drag, position)
+    {
+        view.drag = Some((from, position));
+    } else if mouse.just_released(MouseButton::Left) {
+        let Some((from, to)) = view.drag.take() else {
+            return;
+        };
+        let crop = crop_rect(from, to, view.size);
+        if crop.width >= MIN_CROP && crop.height >= MIN_CROP {
+            update_edit(&mut data.state, index, |edit| edit.crop = Some(crop));
+        }
+    }
+}
+
+// frames extracted by the thread of EditView::load
+pub fn edit_receiver(
+    image_query: Single<(&mut ImageNode, &mut Node), With<EditImage>>,
+    mut images: ResMut<Assets<Image>>,
+    mut view: ResMut<EditView>,
+    mut process_state: ResMut<ProcessState>,
+) {
+    let (mut image_node, mut node) = image_query.into_inner();
+    while let Ok((_secs, frame)) = view.frame_rx.try_recv() {
+        view.loading = false;
+        match frame {
+            Ok(frame) => {
+                let (width, height) = frame.dimensions();
+                let usage = bevy::render::render_asset::RenderAssetUsages::default();
+                let image = image::DynamicImage::ImageRgba8(frame);
+                images.remove(&view.image);
+                view.image = images.add(Image::from_dynamic(image, true, usage));
+                image_node.image = view.image.clone();
+                node.aspect_ratio = Some(width as f32 / height as f32);
+            }
+            Err(e) => process_state.toast_message.push(format!("edit: {}", e)),
+        }
+        // the newest position asked for while this frame was extracted
+        if let Some(secs) = view.pending.take() {
+            view.load(secs);
+        }
+    }
+}
+
+// show or hide the window, the text and the overlays follow the edit
+pub fn edit_window_update(
+    window_query: Single<&mut Visibility, (With<EditWindow>, Without<EditOverlay>)>,
+    mut overlay_query: Query<(&EditOverlay, &mut Node, &mut Visibility), Without<EditWindow>>,
+    mut text_query: Single<&mut Text, With<EditText>>,
+    view: Res<EditView>,
+    data: Res<PathDatas>,
+) {
+    let mut visibility = window_query.into_inner();
+    let shown = if view.index.is_some() {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    if *visibility != shown {
+        *visibility = shown;
+    }
+    let Some(index) = view.index else {
+        return;
+    };
+    if !view.is_changed() && !data.is_changed() {
+        return;
+    }
+    let edit = data.state.edits.get(&index).cloned().unwrap_or_default();
+
+    let summary = if edit.is_empty() {
+        "drag on the frame to crop, I / O set the in and out points".to_string()
+    } else {
+        edit.summary()
+    };
+    let label = format!(
+        "{} / {}  {}{}",
+        timestamp(view.secs),
+        timestamp(view.duration),
+        summary,
+        if view.loading { "  loading..." } else { "" }
+    );
+    if text_query.0 != label {
+        text_query.0 = label;
+    }
+
+    let duration = view.duration.max(0.001);
+    let percent = |secs: f64| Val::Percent((secs / duration * 100.0) as f32);
+    // the crop being drawn, or the stored one
+    let crop = match view.drag {
+        Some((from, to)) => Some(crop_rect(from, to, view.size)),
+        None => edit.crop,
+    };
+    for (overlay, mut node, mut visibility) in overlay_query.iter_mut() {
+        match overlay {
+            EditOverlay::Cursor => node.left = percent(view.secs),
+            EditOverlay::Range => {
+                let start = edit.start.unwrap_or(0.0);
+                let end = edit.end.unwrap_or(view.duration);
+                node.left = percent(start);
+                node.width = percent(end - start);
+            }
+            EditOverlay::CropBox => {
+                let (Some(crop), true) = (crop, view.size.x > 0 && view.size.y > 0) else {
+                    *visibility = Visibility::Hidden;
+                    continue;
+                };
+                let size = view.size.as_vec2();
+                node.left = Val::Percent(crop.x as f32 / size.x * 100.0);
+                node.top = Val::Percent(crop.y as f32 / size.y * 100.0);
+                node.width = Val::Percent(crop.width as f32 / size.x * 100.0);
+                node.height = Val::Percent(crop.height as f32 / size.y * 100.0);
+                *visibility = Visibility::Inherited;
+            }
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use crate::define::*;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+// type into the search box of the filter bar, the list follows each key
+pub fn search_input_system(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut focus: ResMut<InputFocus>,
+    search_query: Single<(Entity, &Children), With<SearchInput>>,
+    mut text_query: Query<&mut Text>,
+    mut filter: ResMut<ListFilter>,
+) {
+    let (entity, children) = *search_query;
+    if focus.0 != Some(entity) {
+        keyboard_events.clear();
+        return;
+    }
+    let Ok(mut text) = text_query.get_mut(children[0]) else {
+        return;
+    };
+    // leave the shortcuts (e.g. paste) to the other systems
+    let modifier = keyboard.any_pressed([
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+    ]);
+
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(c) if !modifier => text.0.push_str(c),
+            Key::Space => text.0.push(' '),
+            Key::Backspace => {
+                text.0.pop();
+            }
+            Key::Enter => focus.0 = None,
+            // clear the search
+            Key::Escape => {
+                text.0.clear();
+                focus.0 = None;
+            }
+            _ => {}
+        }
+    }
+    if filter.search != text.0 {
+        filter.search = text.0.clone();
+    }
+}
@@ -0,0 +1,141 @@
+use crate::define::*;
+use crate::systems::analyze_media;
+use crate::utility::cli::CliOptions;
+use crate::utility::probe_hardware;
+use crate::utility::replace::replace_line;
+use bevy::prelude::*;
+use serde_json::json;
+
+// the same resources as the window mode, the files are queued right away
+pub fn headless_setup(mut commands: Commands, options: Res<CliOptions>) {
+    let mut process_state = ProcessState::new(None);
+
+    let mut ffmpeg_arg = FfmpegArg::load();
+    match probe_hardware() {
+        Ok((backend, encoders)) => {
+            process_state
+                .toast_message
+                .extend(ffmpeg_arg.apply_hardware(backend, &encoders));
+        }
+        Err(e) => process_state.toast_message.push(e),
+    }
+
+    let mut data = PathDatas::default();
+    data.state.lines = options.files.clone();
+    data.state.status = vec![TaskStatus::Queued; options.files.len()];
+
+    let mut queue = TaskQueue {
+        max_parallel: options.parallel,
+        ..default()
+    };
+    let preset = options
+        .preset
+        .clone()
+        .or_else(|| ffmpeg_arg.preset_names().into_iter().next())
+        .unwrap_or_default();
+    for index in 0..options.files.len() {
+        queue.push(index, preset.clone());
+    }
+
+    // the duration of each file is needed for the percent
+    analyze_media(
+        data.state.lines.iter().cloned().enumerate().collect(),
+        process_state.progress_tx.clone(),
+        ffmpeg_arg.analyze.clone(),
+        process_state.toast_tx.clone(),
+    );
+
+    commands.insert_resource(process_state);
+    commands.insert_resource(data);
+    commands.insert_resource(queue);
+    commands.insert_resource(ffmpeg_arg);
+    commands.insert_resource(ProfileRules::load());
+    commands.insert_resource(AppConfig::load());
+    commands.init_resource::<TaskLogs>();
+}
+
+// one JSON object per progress message
+pub fn headless_report(data: Res<PathDatas>, mut changed: EventReader<ProgressChanged>) {
+    for ProgressChanged(index) in changed.read() {
+        let statistics = data.state.progress.get(index).cloned().unwrap_or_default();
+        let line = json!({
+            "index": index,
+            "path": data.state.lines.get(*index),
+            "status": data.state.status.get(*index),
+            "current": statistics.current,
+            "total": statistics.total,
+            "percent": statistics.percent,
+            "fps": statistics.stats.fps,
+            "speed": statistics.stats.speed,
+            "bitrate": statistics.stats.bitrate,
+            "size": statistics.stats.total_size,
+            "eta": statistics.eta,
+            "error": data.state.errors.get(index),
+        });
+        println!("{}", line);
+    }
+}
+
+// replace the sources of the finished files when --replace is given
+pub fn headless_replace(
+    options: Res<CliOptions>,
+    config: Res<AppConfig>,
+    mut data: ResMut<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+    mut changed: EventReader<ProgressChanged>,
+) {
+    for ProgressChanged(index) in changed.read() {
+        if !options.replace || data.state.status.get(*index) != Some(&TaskStatus::Done) {
+            continue;
+        }
+        match replace_line(&mut data.state, *index, &config) {
+            Ok(warnings) => process_state.toast_message.extend(warnings),
+            Err(e) => {
+                // the encoded file is kept, the line is reported as failed
+                data.state.status[*index] = TaskStatus::Failed;
+                data.state
+                    .errors
+                    .insert(*index, format!("replace failed: {}", e));
+            }
+        }
+    }
+}
+
+// toasts are written to stderr
+pub fn headless_messages(mut process_state: ResMut<ProcessState>) {
+    while let Ok(message) = process_state.toast_rx.try_recv() {
+        process_state.toast_message.push(message);
+    }
+    for message in process_state.toast_message.drain(..) {
+        eprintln!("{}", message);
+    }
+}
+
+// exit when every file is finished, with code 1 when one of them failed
+pub fn headless_exit(data: Res<PathDatas>, queue: Res<TaskQueue>, mut exit: EventWriter<AppExit>) {
+    let busy = data
+        .state
+        .status
+        .iter()
+        .any(|status| matches!(status, TaskStatus::Queued | TaskStatus::Running));
+    if busy || !queue.jobs.is_empty() {
+        return;
+    }
+    let failed = data
+        .state
+        .status
+        .iter()
+        .filter(|status| matches!(status, TaskStatus::Failed))
+        .count();
+    let summary = json!({
+        "files": data.state.lines.len(),
+        "failed": failed,
+        "status": data.state.status,
+    });
+    println!("{}", summary);
+    exit.write(if failed > 0 {
+        AppExit::from_code(1)
+    } else {
+        AppExit::Success
+    });
+}
@@ -0,0 +1,165 @@
+use crate::systems::analyze_media;
+use crate::{TOKIO_RT, define::*};
+use bevy::prelude::*;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use tokio::sync::mpsc;
+
+// start a new list before an import of type Once, the queued jobs and the logs belong to
+// the old lines, a running task reports with its index, so like Remove it waits for them
+pub fn clear_lines(
+    data: &mut PathDatas,
+    queue: &mut TaskQueue,
+    logs: &mut TaskLogs,
+) -> Result<(), String> {
+    let running = data
+        .state
+        .status
+        .iter()
+        .any(|status| matches!(status, TaskStatus::Running));
+    if running {
+        return Err("Can not replace the list while tasks are running".to_string());
+    }
+    data.state = FilesState::default();
+    queue.jobs.clear();
+    logs.0.clear();
+    data.changed = true;
+    Ok(())
+}
+
+// add paths to the list, existing lines keep their status, only the new lines are analyzed
+// returns the indexes of the new lines
+pub fn import_lines(
+    data: &mut PathDatas,
+    paths: impl IntoIterator<Item = String>,
+    process_state: &ProcessState,
+    analyze_args: &[ArgKeyValue],
+) -> Range<usize> {
+    let start = data.state.lines.len();
+    for path in paths {
+        let path = path.trim();
+        if !path.is_empty() && !data.state.lines.iter().any(|line| line == path) {
+            data.state.lines.push(path.to_string());
+            data.state.status.push(TaskStatus::Waiting);
+        }
+    }
+    let added = start..data.state.lines.len();
+    if added.is_empty() {
+        return added;
+    }
+    // start analyze media information
+    analyze_media(
+        added
+            .clone()
+            .map(|index| (index, data.state.lines[index].clone()))
+            .collect(),
+        process_state.progress_tx.clone(),
+        analyze_args.to_vec(),
+        process_state.toast_tx.clone(),
+    );
+    data.changed = true;
+    added
+}
+
+// the files found in the dropped or picked directories
+pub const VIDEO_EXTENSIONS: &[&str] = &[
+    "mp4", "mkv", "mov", "avi", "webm", "m4v", "wmv", "flv", "ts", "mts", "m2ts", "mpg", "mpeg",
+    "3gp",
+];
+
+// files are kept, directories are replaced by their video files
+pub fn collect_media_files(paths: Vec<PathBuf>) -> Vec<String> {
+    let mut files = vec![];
+    for path in paths {
+        if path.is_dir() {
+            collect_dir(&path, &mut files);
+        } else {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+    files
+}
+
+fn collect_dir(dir: &Path, files: &mut Vec<String>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            collect_dir(&path, files);
+        } else if path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| VIDEO_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str()))
+        {
+            files.push(path.to_string_lossy().to_string());
+        }
+    }
+}
+
+// the native file dialog, the chosen files are sent to `import_receiver`
+pub fn pick_files(import_tx: mpsc::Sender<Vec<String>>) {
+    let dialog = rfd::AsyncFileDialog::new()
+        .set_title("Import")
+        .add_filter("Video", VIDEO_EXTENSIONS)
+        .pick_files();
+    std::thread::spawn(move || {
+        let Some(handles) = TOKIO_RT.block_on(dialog) else {
+            return; // cancelled
+        };
+        let paths = handles.iter().map(|handle| handle.path().to_path_buf());
+        let _ = import_tx.blocking_send(collect_media_files(paths.collect()));
+    });
+}
+
+// the files dropped on the window, the directories are read in another thread
+pub fn file_drop(mut drop_events: EventReader<FileDragAndDrop>, process_state: Res<ProcessState>) {
+    let paths: Vec<PathBuf> = drop_events
+        .read()
+        .filter_map(|event| match event {
+            FileDragAndDrop::DroppedFile { path_buf, .. } => Some(path_buf.clone()),
+            _ => None,
+        })
+        .collect();
+    if paths.is_empty() {
+        return;
+    }
+    let import_tx = process_state.import_tx.clone();
+    std::thread::spawn(move || {
+        let _ = import_tx.blocking_send(collect_media_files(paths));
+    });
+}
+
+// import the dropped and picked files like a paste
+pub fn import_receiver(
+    mut data: ResMut<PathDatas>,
+    mut queue: ResMut<TaskQueue>,
+    mut logs: ResMut<TaskLogs>,
+    process_menu: Res<ProcessMenu>,
+    mut process_state: ResMut<ProcessState>,
+    ffmpeg_args: Res<FfmpegArg>,
+) {
+    while let Ok(files) = process_state.import_rx.try_recv() {
+        match process_menu.import_type {
+            MenuImportButton::Lock => {
+                process_state
+                    .toast_message
+                    .push("Import is locked".to_string());
+                continue;
+            }
+            MenuImportButton::Once => {
+                if let Err(e) = clear_lines(&mut data, &mut queue, &mut logs) {
+                    process_state.toast_message.push(e);
+                    continue;
+                }
+            }
+            MenuImportButton::Sequence => {}
+        }
+        let added = import_lines(&mut data, files, &process_state, &ffmpeg_args.analyze);
+        process_state
+            .toast_message
+            .push(format!("{} files imported", added.len()));
+    }
+}
@@ -1,6 +1,11 @@
 use crate::define::*;
-use crate::utility::task::{open_dir, replace, snapshot, task};
+use crate::utility::platform::{copy_to_clipboard, open_path, reveal_file};
+use crate::utility::replace::{replace_line, undo_replace};
+use crate::utility::sheet::{cell_rects, contact_sheet};
+use crate::utility::task::snapshot;
 use bevy::prelude::*;
+use bevy::ui::RelativeCursorPosition;
+use std::path::{Path, PathBuf};
 
 // set task_button text content according to the status
 pub fn update_task_button_text(
@@ -17,18 +22,32 @@ pub fn update_task_button_text(
         };
         // update the text content according to the status
         if let Ok(mut text) = text_query.get_mut(*childen_entity) {
-            text.0 = match data.state.status.get(idx.0).unwrap_or(&TaskStatus::Waiting) {
+            let label = match data.state.status.get(idx.0).unwrap_or(&TaskStatus::Waiting) {
                 TaskStatus::Waiting => {
-                    if btty.0 {
-                        "sf".into()
+                    // mark the preset chosen by the rules
+                    if data.state.profiles.get(&idx.0) == Some(&btty.0) {
+                        format!("{}*", btty.0)
                     } else {
-                        "hw".into()
+                        btty.0.clone()
                     }
                 }
+                TaskStatus::Queued => "queue".to_string(),
                 TaskStatus::Running => "...".to_string(),
                 TaskStatus::Done => "done".to_string(),
                 TaskStatus::Replaced => "moved".to_string(),
+                TaskStatus::Failed => "failed".to_string(),
+                TaskStatus::Cancelled => "cancel".to_string(),
+                TaskStatus::Skipped => "skip".to_string(),
+                // the preset of the partial output goes on, the others start again
+                TaskStatus::Interrupted if data.state.resumable(idx.0, &btty.0) => {
+                    "resume".to_string()
+                }
+                TaskStatus::Interrupted => "restart".to_string(),
             };
+            // the text is only marked changed when the status changed
+            if text.0 != label {
+                text.0 = label;
+            }
         }
     }
 
@@ -48,47 +67,64 @@ pub fn task_interaction(
         (Changed<Interaction>, With<TaskButton>),
     >,
     mut data: ResMut<PathDatas>,
+    mut queue: ResMut<TaskQueue>,
     process_state: Res<ProcessState>,
-    ffmpeg_args: Res<FfmpegArg>,
 ) -> Result {
     for (_entity, interaction, idx, mut bg, btty) in interaction_query.iter_mut() {
-        let Some(path) = data.state.lines.get(idx.0).cloned() else {
+        let resume = data.state.resumable(idx.0, &btty.0);
+        let Some(status) = data.state.status.get_mut(idx.0) else {
             return Ok(());
         };
-        let status = &mut data.state.status[idx.0];
         match *interaction {
             Interaction::Hovered => {
-                // set background color when the status is Waiting,otherwise do not change
-                if matches!(status, TaskStatus::Waiting) {
+                // set background color when the task can be queued,otherwise do not change
+                if matches!(
+                    status,
+                    TaskStatus::Waiting
+                        | TaskStatus::Failed
+                        | TaskStatus::Cancelled
+                        | TaskStatus::Skipped
+                        | TaskStatus::Interrupted
+                ) {
                     *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
                 }
             }
             Interaction::Pressed => {
                 match status {
-                    // start a task when status is Waiting
-                    TaskStatus::Waiting => {
-                        data.state.status[idx.0] = TaskStatus::Running;
+                    // put the task into the queue,the scheduler starts it when a slot is free
+                    // a skipped line can still be started by hand
+                    TaskStatus::Waiting
+                    | TaskStatus::Failed
+                    | TaskStatus::Cancelled
+                    | TaskStatus::Skipped
+                    | TaskStatus::Interrupted => {
+                        *status = TaskStatus::Queued;
                         *bg = BackgroundColor(Color::srgb_u8(64, 84, 64));
-                        let args = if btty.0 {
-                            ffmpeg_args.sf_convert.clone()
+                        if resume {
+                            queue.resume(idx.0, btty.0.clone());
                         } else {
-                            ffmpeg_args.hw_convert.clone()
-                        };
-                        task(idx.0, &process_state, path, btty.0, args);
-                        continue;
+                            queue.push(idx.0, btty.0.clone());
+                        }
                     }
-                    // if the status is Running, stop the task
+                    // take the task out of the queue
+                    TaskStatus::Queued => {
+                        *status = TaskStatus::Waiting;
+                        *bg = BackgroundColor(Color::srgb_u8(84, 84, 84));
+                        queue.remove(idx.0);
+                    }
+                    // if the status is Running, interrupt the task
+                    // the task thread kills ffmpeg and reports back with Cancelled
                     TaskStatus::Running => {
                         *bg = BackgroundColor(Color::srgb_u8(84, 84, 84));
-                        // TODO:  or interrupt task
+                        let _ = process_state
+                            .main_tx
+                            .send(ProcessSignal::TaskInterrupt(idx.0));
                     }
                     // skip if the status is Done and Replaced
                     TaskStatus::Done | TaskStatus::Replaced => {
                         continue;
                     }
                 }
-
-                if matches!(status, TaskStatus::Running) {}
             }
             Interaction::None => match status {
                 // how to revert the background color change according to the status
@@ -96,6 +132,10 @@ pub fn task_interaction(
                     // default
                     *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
                 }
+                TaskStatus::Queued => {
+                    // (yellow)
+                    *bg = BackgroundColor(Color::srgb_u8(128, 128, 32));
+                }
                 TaskStatus::Running => {
                     // (green)
                     *bg = BackgroundColor(Color::srgb_u8(32, 128, 32));
@@ -104,6 +144,22 @@ pub fn task_interaction(
                     // (blue)
                     *bg = BackgroundColor(Color::srgb_u8(32, 32, 128));
                 }
+                TaskStatus::Failed => {
+                    // (red)
+                    *bg = BackgroundColor(Color::srgb_u8(128, 32, 32));
+                }
+                TaskStatus::Cancelled => {
+                    // (gray)
+                    *bg = BackgroundColor(Color::srgb_u8(64, 64, 64));
+                }
+                TaskStatus::Skipped => {
+                    // (dark blue)
+                    *bg = BackgroundColor(Color::srgb_u8(24, 24, 64));
+                }
+                TaskStatus::Interrupted => {
+                    // (orange)
+                    *bg = BackgroundColor(Color::srgb_u8(128, 80, 32));
+                }
             },
         }
     }
@@ -117,31 +173,80 @@ pub fn replace_interaction(
         (Changed<Interaction>, With<ReplaceButton>),
     >,
     mut data: ResMut<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+    config: Res<AppConfig>,
 ) -> Result {
     for (_entity, interaction, idx, mut bg) in interaction_query.iter_mut() {
         let has_done =
             data.state.status.get(idx.0).unwrap_or(&TaskStatus::Waiting) == &TaskStatus::Done;
-        let Some(path) = data.state.lines.get(idx.0).cloned() else {
-            return Ok(());
-        };
-        let status = &mut data.state.status[idx.0];
-        if matches!(status, TaskStatus::Replaced) {
-            continue;
-        }
+        let quality = data.state.quality_check(idx.0, &config.quality);
         match *interaction {
             Interaction::Hovered => {
-                if !has_done {
+                if !has_done || quality.is_err() {
                     continue;
                 }
                 *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
             }
             Interaction::Pressed => {
+                // replace the source file only when the status is Done
                 if !has_done {
                     continue;
                 }
-                // replace the source file only when the status is Done
-                if matches!(status, TaskStatus::Done) {
-                    replace(idx.0, path, &mut data);
+                if let Err(e) = quality {
+                    process_state
+                        .toast_message
+                        .push(format!("Replace blocked: {}", e));
+                    continue;
+                }
+                match replace_line(&mut data.state, idx.0, &config) {
+                    Ok(warnings) => process_state.toast_message.extend(warnings),
+                    Err(e) => process_state
+                        .toast_message
+                        .push(format!("Replace failed: {}", e)),
+                }
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
+            }
+        }
+    }
+    Ok(())
+}
+
+// undo replace button interaction
+pub fn undo_replace_interaction(
+    mut interaction_query: Query<
+        (Entity, &Interaction, &IndexOfline, &mut BackgroundColor),
+        (Changed<Interaction>, With<UndoReplaceButton>),
+    >,
+    mut data: ResMut<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+) -> Result {
+    for (_entity, interaction, idx, mut bg) in interaction_query.iter_mut() {
+        let has_replaced = data.state.status.get(idx.0) == Some(&TaskStatus::Replaced)
+            && data.state.replaced.contains_key(&idx.0);
+        match *interaction {
+            Interaction::Hovered => {
+                if !has_replaced {
+                    continue;
+                }
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                let Some(record) = data.state.replaced.get(&idx.0) else {
+                    continue;
+                };
+                match undo_replace(record) {
+                    Ok(warnings) => {
+                        data.state.status[idx.0] = TaskStatus::Done;
+                        data.state.replaced.remove(&idx.0);
+                        process_state.toast_message.extend(warnings);
+                    }
+                    Err(e) => {
+                        process_state
+                            .toast_message
+                            .push(format!("Undo replace failed: {}", e));
+                    }
                 }
             }
             Interaction::None => {
@@ -153,8 +258,8 @@ pub fn replace_interaction(
 }
 
 // snapshot button interaction
+// one frame, or a contact sheet when the Sheet menu is on, made in a thread
 pub fn snapshot_interaction(
-    mut commands: Commands,
     mut interaction_query: Query<
         (
             Entity,
@@ -166,9 +271,10 @@ pub fn snapshot_interaction(
         (Changed<Interaction>, With<SnapshotButton>),
     >,
     data: Res<PathDatas>,
-    preview_query: Single<Entity, With<PreviewWindow>>,
-    mut images: ResMut<Assets<Image>>,
+    process_menu: Res<ProcessMenu>,
+    process_state: Res<ProcessState>,
     ffmpeg_arg: Res<FfmpegArg>,
+    config: Res<AppConfig>,
 ) -> Result {
     for (_entity, interaction, idx, mut bg, source) in interaction_query.iter_mut() {
         let Some(path) = data.state.lines.get(idx.0).cloned() else {
@@ -191,23 +297,143 @@ pub fn snapshot_interaction(
                     continue;
                 }
                 let args = ffmpeg_arg.snapshot.clone();
-                let buf = snapshot(path, source.0, total_secs, args);
-                let img = image::load_from_memory(&buf).unwrap();
-                let preview_entity = *preview_query;
-                let bevy_img = bevy::image::Image::from_dynamic(
-                    img,
-                    true,
-                    bevy::render::render_asset::RenderAssetUsages::default(),
-                );
-                let handle = images.add(bevy_img);
+                // the snapshot of the source or the encoded file
+                let file = if source.0 {
+                    path
+                } else {
+                    let Some(output) = output_path(&data.state, idx.0) else {
+                        continue;
+                    };
+                    output.to_string_lossy().to_string()
+                };
+                let duration = data
+                    .state
+                    .media
+                    .get(&idx.0)
+                    .map_or(total_secs as f64, |info| info.duration);
+                let sheet_config = process_menu.contact_sheet.then(|| config.sheet.clone());
+                let preview_tx = process_state.preview_tx.clone();
+                let toast_tx = process_state.toast_tx.clone();
+                std::thread::spawn(move || {
+                    let preview =
+                        match sheet_config {
+                            Some(sheet_config) => contact_sheet(&file, duration, &sheet_config)
+                                .map(|image| PreviewImage {
+                                    image,
+                                    columns: sheet_config.columns,
+                                    cells: sheet_config.frames,
+                                }),
+                            None => snapshot(file, total_secs, args).and_then(|png| {
+                                let image =
+                                    image::load_from_memory(&png).map_err(|e| e.to_string())?;
+                                Ok(PreviewImage {
+                                    image: image.to_rgba8(),
+                                    columns: 1,
+                                    cells: 0,
+                                })
+                            }),
+                        };
+                    match preview {
+                        Ok(preview) => {
+                            let _ = preview_tx.blocking_send(preview);
+                        }
+                        Err(e) => {
+                            let _ = toast_tx.blocking_send(format!("Snapshot failed: {}", e));
+                        }
+                    }
+                });
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
+            }
+        }
+    }
+    Ok(())
+}
 
-                commands.entity(preview_entity).insert((
-                    Visibility::Visible,
-                    ImageNode {
-                        image: handle,
-                        ..default()
-                    },
-                ));
+// show the images made in a thread, the cells of a grid are kept for scrubbing
+pub fn preview_receiver(
+    mut commands: Commands,
+    mut process_state: ResMut<ProcessState>,
+    preview_query: Single<Entity, With<PreviewWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    mut grid: ResMut<PreviewGrid>,
+) {
+    let usage = bevy::render::render_asset::RenderAssetUsages::default();
+    while let Ok(preview) = process_state.preview_rx.try_recv() {
+        grid.cells = cell_rects(&preview.image, preview.columns, preview.cells)
+            .into_iter()
+            .map(|(x, y, width, height)| {
+                let cell =
+                    image::imageops::crop_imm(&preview.image, x, y, width, height).to_image();
+                let cell = image::DynamicImage::ImageRgba8(cell);
+                images.add(Image::from_dynamic(cell, true, usage))
+            })
+            .collect();
+        grid.columns = preview.columns;
+        grid.hovered = None;
+        let image = image::DynamicImage::ImageRgba8(preview.image);
+        grid.image = images.add(Image::from_dynamic(image, true, usage));
+        commands.entity(*preview_query).insert((
+            Visibility::Visible,
+            ImageNode {
+                image: grid.image.clone(),
+                ..default()
+            },
+        ));
+    }
+}
+
+// hover a grid in the preview window to see one cell in full size
+pub fn preview_scrub(
+    preview_query: Single<(&RelativeCursorPosition, &mut ImageNode), With<PreviewWindow>>,
+    mut grid: ResMut<PreviewGrid>,
+) {
+    if grid.cells.is_empty() {
+        return;
+    }
+    let (cursor, mut image_node) = preview_query.into_inner();
+    let columns = grid.columns.max(1) as usize;
+    let rows = grid.cells.len().div_ceil(columns);
+    // the position is relative to the center of the node
+    let hovered = cursor
+        .normalized
+        .filter(|_| cursor.cursor_over())
+        .map(|position| {
+            let x = ((position.x + 0.5).clamp(0.0, 0.999) * columns as f32) as usize;
+            let y = ((position.y + 0.5).clamp(0.0, 0.999) * rows as f32) as usize;
+            (y * columns + x).min(grid.cells.len() - 1)
+        });
+    if hovered == grid.hovered {
+        return;
+    }
+    grid.hovered = hovered;
+    image_node.image = match hovered {
+        Some(cell) => grid.cells[cell].clone(),
+        None => grid.image.clone(),
+    };
+}
+
+// details button interaction
+pub fn details_interaction(
+    mut interaction_query: Query<
+        (Entity, &Interaction, &IndexOfline, &mut BackgroundColor),
+        (Changed<Interaction>, With<DetailsButton>),
+    >,
+    mut process_menu: ResMut<ProcessMenu>,
+) -> Result {
+    for (_entity, interaction, idx, mut bg) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                // expand or collapse the details of this line
+                process_menu.details_index = if process_menu.details_index == Some(idx.0) {
+                    None
+                } else {
+                    Some(idx.0)
+                };
             }
             Interaction::None => {
                 *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
@@ -224,6 +450,7 @@ pub fn opendir_interaction(
         (Changed<Interaction>, With<OpenButton>),
     >,
     data: Res<PathDatas>,
+    process_state: Res<ProcessState>,
 ) -> Result {
     for (_entity, interaction, idx, mut bg) in interaction_query.iter_mut() {
         let Some(path) = data.state.lines.get(idx.0).cloned() else {
@@ -234,9 +461,85 @@ pub fn opendir_interaction(
                 *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
             }
             Interaction::Pressed => {
-                // replace the source file only when the status is Done
-                open_dir(path);
+                // open the directory of the source file
+                let dir = Path::new(&path).parent().unwrap_or(Path::new(&path));
+                open_path(dir, process_state.toast_tx.clone());
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
+            }
+        }
+    }
+    Ok(())
+}
+
+// the encoded file stays where the task wrote it until it replaces the source
+fn output_path(state: &FilesState, index: usize) -> Option<PathBuf> {
+    match state.replaced.get(&index) {
+        Some(record) if state.status.get(index) == Some(&TaskStatus::Replaced) => {
+            Some(record.target.clone())
+        }
+        _ => state.outputs.get(&index).cloned(),
+    }
+}
+
+// reveal output button interaction
+pub fn reveal_interaction(
+    mut interaction_query: Query<
+        (Entity, &Interaction, &IndexOfline, &mut BackgroundColor),
+        (Changed<Interaction>, With<RevealButton>),
+    >,
+    data: Res<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+) -> Result {
+    for (_entity, interaction, idx, mut bg) in interaction_query.iter_mut() {
+        let Some(path) = data.state.lines.get(idx.0).cloned() else {
+            return Ok(());
+        };
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                let Some(output) = output_path(&data.state, idx.0) else {
+                    process_state
+                        .toast_message
+                        .push(format!("No output file for {}", path));
+                    continue;
+                };
+                reveal_file(&output, process_state.toast_tx.clone());
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
+            }
+        }
+    }
+    Ok(())
+}
+
+// copy path button interaction
+pub fn copy_path_interaction(
+    mut interaction_query: Query<
+        (Entity, &Interaction, &IndexOfline, &mut BackgroundColor),
+        (Changed<Interaction>, With<CopyPathButton>),
+    >,
+    data: Res<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+) -> Result {
+    for (_entity, interaction, idx, mut bg) in interaction_query.iter_mut() {
+        let Some(path) = data.state.lines.get(idx.0).cloned() else {
+            return Ok(());
+        };
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
             }
+            Interaction::Pressed => match copy_to_clipboard(&path) {
+                Ok(()) => process_state.toast_message.push(format!("Copied {}", path)),
+                Err(e) => process_state
+                    .toast_message
+                    .push(format!("Failed to copy path: {}", e)),
+            },
             Interaction::None => {
                 *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
             }
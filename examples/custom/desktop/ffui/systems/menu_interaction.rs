@@ -1,4 +1,6 @@
 use crate::define::*;
+use crate::systems::pick_files;
+use crate::utility::session::recover_lines;
 use bevy::prelude::*;
 
 // menu button interaction
@@ -20,6 +22,9 @@ pub fn menu_interaction(
     mut data: ResMut<PathDatas>,
     mut app_state: ResMut<NextState<AppState>>,
     mut process_state: ResMut<ProcessState>,
+    mut queue: ResMut<TaskQueue>,
+    mut filter: ResMut<ListFilter>,
+    mut config: ResMut<AppConfig>,
 ) -> Result {
     for (_entity, interaction, _name, mut bg, mut mb) in interaction_query.iter_mut() {
         let button_type = mb.button_type.as_mut();
@@ -40,6 +45,9 @@ pub fn menu_interaction(
                         process_menu.import_type
                     ));
                 }
+                if button_type.as_any_mut().is::<MenuImportFilesButton>() {
+                    pick_files(process_state.import_tx.clone());
+                }
                 if button_type.as_any_mut().is::<MenuLoadButton>() {
                     //info!("Load button pressed");
                     let Ok(json) = std::fs::read_to_string("files_state.json") else {
@@ -58,6 +66,9 @@ pub fn menu_interaction(
                     };
                     data.state = state;
                     data.changed = true;
+                    // the queue is not saved, queued lines go back to waiting
+                    queue.jobs.clear();
+                    recover_lines(&mut data.state);
                 }
                 if button_type.as_any_mut().is::<MenuSaveButton>() {
                     //info!("Save button pressed");
@@ -83,17 +94,92 @@ pub fn menu_interaction(
                 }
                 if button_type.as_any_mut().is::<MenuClearButton>() {
                     commands.entity(*preview_query).insert(Visibility::Hidden);
+                    process_menu.log_index = None;
+                    process_menu.details_index = None;
                     process_state
                         .toast_message
                         .push("Preview window cleared".to_string());
                     //info!("Clear preview window");
                 }
-                if button_type.as_any_mut().is::<MenuHideButton>() {
-                    process_menu.hide_done = checked;
-                    //info!("Hide done tasks: {}", process_menu.hide_done);
+                if button_type.as_any_mut().is::<MenuSheetButton>() {
+                    process_menu.contact_sheet = checked;
+                    process_state
+                        .toast_message
+                        .push(format!("Contact sheet: {}", process_menu.contact_sheet));
+                }
+                if button_type.as_any_mut().is::<MenuQualityButton>() {
+                    config.quality.enabled = checked;
+                    process_state.toast_message.push(format!(
+                        "Quality check ({}): {}",
+                        config.quality.metric.name(),
+                        checked
+                    ));
+                    if let Err(e) = config.save() {
+                        process_state.toast_message.push(e);
+                    }
+                }
+                if let Some(bt) = button_type
+                    .as_any_mut()
+                    .downcast_mut::<MenuThresholdButton>()
+                {
+                    config.quality.metric = bt.metric;
+                    config.quality.threshold = Some(bt.threshold);
                     process_state
                         .toast_message
-                        .push(format!("Hide done tasks: {}", process_menu.hide_done));
+                        .push(format!("{}, replace is blocked below", bt));
+                    if let Err(e) = config.save() {
+                        process_state.toast_message.push(e);
+                    }
+                }
+                if let Some(bt) = button_type.as_any_mut().downcast_mut::<MenuFilterButton>() {
+                    filter.status = bt.0;
+                }
+                if let Some(bt) = button_type.as_any_mut().downcast_mut::<MenuSortButton>() {
+                    filter.sort = bt.0;
+                }
+                if button_type.as_any_mut().is::<MenuOrderButton>() {
+                    filter.descending = checked;
+                }
+                if button_type.as_any_mut().is::<MenuStartAllButton>() {
+                    let mut count = 0;
+                    for (index, status) in data.state.status.iter_mut().enumerate() {
+                        if matches!(status, TaskStatus::Waiting) {
+                            *status = TaskStatus::Queued;
+                            // use the preset chosen by the rules, or the one picked in the menu
+                            let preset = data
+                                .state
+                                .profiles
+                                .get(&index)
+                                .cloned()
+                                .unwrap_or_else(|| process_menu.preset.clone());
+                            queue.push(index, preset);
+                            count += 1;
+                        }
+                    }
+                    process_state
+                        .toast_message
+                        .push(format!("{} tasks queued", count));
+                }
+                if let Some(bt) = button_type.as_any_mut().downcast_mut::<MenuPresetButton>() {
+                    process_menu.preset = bt.current().to_string();
+                    process_state
+                        .toast_message
+                        .push(format!("Preset changed to: {}", process_menu.preset));
+                }
+                if button_type.as_any_mut().is::<MenuPauseButton>() {
+                    queue.paused = checked;
+                    process_state
+                        .toast_message
+                        .push(format!("Queue paused: {}", queue.paused));
+                }
+                if let Some(bt) = button_type
+                    .as_any_mut()
+                    .downcast_mut::<MenuParallelButton>()
+                {
+                    queue.max_parallel = bt.count;
+                    process_state
+                        .toast_message
+                        .push(format!("Max parallel tasks: {}", queue.max_parallel));
                 }
                 if button_type.as_any_mut().is::<MenuToggleSetting>() {
                     process_menu.toggle_setting = checked;
@@ -127,9 +213,6 @@ pub fn menu_interaction(
                 // if button_type.as_any_mut().is::<MenuClearButton>() {
                 //     *bg = BackgroundColor(Color::srgb_u8(64, 64, 64));
                 // }
-                // if button_type.as_any_mut().is::<MenuHideButton>() {
-                //     *bg = BackgroundColor(Color::srgb_u8(64, 64, 64));
-                // }
                 // if button_type.as_any_mut().is::<MenuExitButton>() {
                 //     *bg = BackgroundColor(Color::srgb_u8(64, 64, 64));
                 // }
@@ -12,3 +12,42 @@ pub use app_window::*;
 
 pub mod shortcuts;
 pub use shortcuts::*;
+
+pub mod import;
+pub use import::*;
+
+pub mod watch;
+pub use watch::*;
+
+pub mod session;
+pub use session::*;
+
+pub mod filter;
+pub use filter::*;
+
+pub mod bulk;
+pub use bulk::*;
+
+pub mod compare;
+pub use compare::*;
+
+pub mod edit;
+pub use edit::*;
+
+pub mod quality;
+pub use quality::*;
+
+pub mod task_queue;
+pub use task_queue::*;
+
+pub mod task_log;
+pub use task_log::*;
+
+pub mod setting_interaction;
+pub use setting_interaction::*;
+
+pub mod progress;
+pub use progress::*;
+
+pub mod headless;
+pub use headless::*;
@@ -0,0 +1,133 @@
+use crate::define::*;
+use bevy::prelude::*;
+
+// apply the progress messages of the task threads to FilesState
+// the ui (or the headless report) follows the ProgressChanged events
+pub fn progress_receiver(
+    mut process_state: ResMut<ProcessState>,
+    mut paths_data: ResMut<PathDatas>,
+    rules: Res<ProfileRules>,
+    mut changed: EventWriter<ProgressChanged>,
+) {
+    while let Ok(message) = process_state.progress_rx.try_recv() {
+        let Some(mut idx) = message.progress_index else {
+            continue;
+        };
+        // the lines may have moved (Remove) or been replaced (import Once, Load) since the
+        // analysis started, its result belongs to the line with the same path
+        if let Some(path) = &message.progress_path
+            && paths_data.state.lines.get(idx) != Some(path)
+        {
+            match paths_data.state.lines.iter().position(|line| line == path) {
+                Some(index) => idx = index,
+                None => continue,
+            }
+        }
+        if idx >= paths_data.state.status.len() {
+            continue;
+        }
+
+        // a cancelled or failed task may still have progress messages in the channel
+        let running = matches!(paths_data.state.status.get(idx), Some(TaskStatus::Running));
+
+        // reborrow, so the fields of the state can be borrowed separately
+        let paths_data = &mut *paths_data;
+        let statistics = paths_data.state.progress.entry(idx).or_default();
+
+        let mut status = None;
+        let mut apply_rules = false;
+
+        match message.progress_type {
+            ProgressType::Total => {
+                statistics.total = message.progress_value;
+                statistics.current = 0; // reset current when total is set
+            }
+            ProgressType::Current => {
+                statistics.current = message.progress_value;
+            }
+            ProgressType::Stats(stats) => {
+                // estimate the remaining time from the encoding speed
+                statistics.eta = if stats.speed > 0.0 && statistics.total > 0 {
+                    // the passes after this one encode the whole duration again
+                    let passes_left = statistics
+                        .pass
+                        .map_or(0, |(pass, passes)| passes.saturating_sub(pass));
+                    let remaining = statistics.total.saturating_sub(statistics.current)
+                        + statistics.total * passes_left as u64;
+                    Some((remaining as f64 / stats.speed) as u64)
+                } else {
+                    None
+                };
+                statistics.stats = stats;
+            }
+            ProgressType::Cancelled => {
+                statistics.current = 0;
+                statistics.eta = None;
+                statistics.pass = None;
+                status = Some(TaskStatus::Cancelled);
+            }
+            ProgressType::Finished => {
+                // progress=end is the authoritative completion signal
+                statistics.current = statistics.total;
+                statistics.eta = None;
+                statistics.pass = None;
+                if running {
+                    status = Some(TaskStatus::Done);
+                }
+                // the first part is the output of a segmented task, the muxer may write none
+                if running
+                    && let Some(pattern) = paths_data.state.segments.get(&idx)
+                    && segment_parts(pattern).is_empty()
+                {
+                    status = Some(TaskStatus::Failed);
+                    paths_data
+                        .state
+                        .errors
+                        .insert(idx, format!("no segments written: {}", pattern.display()));
+                }
+            }
+            ProgressType::Pass(pass, passes) => {
+                statistics.pass = Some((pass, passes));
+                statistics.current = 0;
+            }
+            ProgressType::Media(info) => {
+                paths_data.state.media.insert(idx, info);
+                apply_rules = true;
+            }
+            ProgressType::Failed(msg) => {
+                statistics.eta = None;
+                status = Some(TaskStatus::Failed);
+                paths_data.state.errors.insert(idx, msg);
+            }
+        }
+
+        // update percent, each pass of a two-pass encode is a part of the bar
+        statistics.percent = if statistics.total > 0 {
+            let part = (statistics.current as f64 / statistics.total as f64).min(1.0);
+            match statistics.pass {
+                Some((pass, passes)) => {
+                    (pass - 1) as f64 / passes as f64 * 100.0 + part * 100.0 / passes as f64
+                }
+                None => part * 100.0,
+            }
+        } else {
+            0.0
+        };
+
+        if let Some(status) = status {
+            paths_data.state.status[idx] = status;
+            // nothing is left to resume, a part kept aside by a cancelled or failed resume goes
+            if let Some(partial) = paths_data.state.partials.remove(&idx)
+                && paths_data.state.status[idx] != TaskStatus::Done
+                && paths_data.state.outputs.get(&idx) != Some(&partial.path)
+            {
+                let _ = std::fs::remove_file(&partial.path);
+            }
+        }
+        // choose the profile (or skip) once the media information is known
+        if apply_rules {
+            rules.apply(idx, &mut paths_data.state);
+        }
+        changed.write(ProgressChanged(idx));
+    }
+}
@@ -0,0 +1,81 @@
+use crate::define::*;
+use crate::utility::quality::measure_quality;
+use bevy::prelude::*;
+
+// measure the quality of each output when its task is done
+pub fn quality_trigger(
+    mut changed: EventReader<ProgressChanged>,
+    mut data: ResMut<PathDatas>,
+    process_state: Res<ProcessState>,
+    config: Res<AppConfig>,
+) {
+    for ProgressChanged(index) in changed.read() {
+        let index = *index;
+        if !config.quality.enabled
+            || data.state.status.get(index) != Some(&TaskStatus::Done)
+            || data.state.quality.contains_key(&index)
+        {
+            continue;
+        }
+        // the frames of a trimmed, cropped or segmented output do not match the source
+        if data
+            .state
+            .edits
+            .get(&index)
+            .is_some_and(|edit| !edit.is_empty())
+        {
+            continue;
+        }
+        let (Some(source), Some(output)) = (
+            data.state.lines.get(index).cloned(),
+            data.state.outputs.get(&index).cloned(),
+        ) else {
+            continue;
+        };
+        data.state.quality.insert(index, QualityScore::Measuring);
+        let metric = config.quality.metric;
+        let quality_tx = process_state.quality_tx.clone();
+        std::thread::spawn(move || {
+            let result = measure_quality(&source, &output.to_string_lossy(), metric);
+            let _ = quality_tx.blocking_send((index, output, result));
+        });
+    }
+}
+
+// store the scores, a score below the threshold blocks the replace of the line
+pub fn quality_receiver(
+    mut process_state: ResMut<ProcessState>,
+    mut data: ResMut<PathDatas>,
+    config: Res<AppConfig>,
+) {
+    while let Ok((index, output, result)) = process_state.quality_rx.try_recv() {
+        // the line was removed or encoded again while it was measured
+        if data.state.outputs.get(&index) != Some(&output)
+            || !matches!(
+                data.state.quality.get(&index),
+                Some(QualityScore::Measuring)
+            )
+        {
+            continue;
+        }
+        let score = match result {
+            Ok((metric, score)) => QualityScore::Score(metric, score),
+            Err(e) => {
+                process_state.toast_message.push(format!(
+                    "Quality of line {} not measured: {}",
+                    index + 1,
+                    e
+                ));
+                QualityScore::Failed(e)
+            }
+        };
+        data.state.quality.insert(index, score);
+        if let Err(e) = data.state.quality_check(index, &config.quality) {
+            process_state.toast_message.push(format!(
+                "Line {}: {}, replace is blocked",
+                index + 1,
+                e
+            ));
+        }
+    }
+}
@@ -0,0 +1,80 @@
+use crate::define::*;
+use crate::utility::session::{load_session, recover_lines, save_session};
+use bevy::platform::collections::HashMap;
+use bevy::prelude::*;
+
+// restore the session of the last run
+pub fn restore_session(
+    mut data: ResMut<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+    mut changed: EventWriter<ProgressChanged>,
+) {
+    let state = match load_session() {
+        Ok(Some(state)) => state,
+        Ok(None) => return,
+        Err(e) => {
+            process_state.toast_message.push(e);
+            return;
+        }
+    };
+    data.state = state;
+    data.changed = true;
+    let interrupted = recover_lines(&mut data.state);
+    // the quality measurements cut off by the exit start again
+    let measuring: Vec<usize> = data
+        .state
+        .quality
+        .iter()
+        .filter(|(_, score)| matches!(score, QualityScore::Measuring))
+        .map(|(index, _)| *index)
+        .collect();
+    for index in measuring {
+        data.state.quality.remove(&index);
+        changed.write(ProgressChanged(index));
+    }
+    process_state.toast_message.push(format!(
+        "Session restored: {} files",
+        data.state.lines.len()
+    ));
+    if interrupted > 0 {
+        process_state.toast_message.push(format!(
+            "{} tasks were interrupted, press resume or restart to encode the rest or all again",
+            interrupted
+        ));
+    }
+}
+
+// save the state whenever a line is added, changes its status, gets a quality score or is edited
+pub fn autosave_session(
+    data: Res<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+    mut saved: Local<(Vec<TaskStatus>, usize, HashMap<usize, FileEdit>)>,
+    mut failed: Local<bool>,
+) {
+    if !data.is_changed() {
+        return;
+    }
+    let measured = data
+        .state
+        .quality
+        .values()
+        .filter(|score| !matches!(score, QualityScore::Measuring))
+        .count();
+    if saved.0 == data.state.status && saved.1 == measured && saved.2 == data.state.edits {
+        return;
+    }
+    saved.0.clone_from(&data.state.status);
+    saved.1 = measured;
+    saved.2.clone_from(&data.state.edits);
+    match save_session(&data.state) {
+        Ok(()) => *failed = false,
+        // report once, not on every change
+        Err(e) if !*failed => {
+            *failed = true;
+            process_state
+                .toast_message
+                .push(format!("Autosave failed: {}", e));
+        }
+        Err(_) => {}
+    }
+}
@@ -0,0 +1,241 @@
+use crate::define::*;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::input_focus::InputFocus;
+use bevy::prelude::*;
+
+// enable or disable a rule, then choose the profiles again
+pub fn rule_toggle_interaction(
+    mut interaction_query: Query<
+        (
+            &Interaction,
+            &RuleToggleButton,
+            &mut BackgroundColor,
+            &Children,
+        ),
+        Changed<Interaction>,
+    >,
+    mut text_query: Query<&mut Text>,
+    mut rules: ResMut<ProfileRules>,
+    mut data: ResMut<PathDatas>,
+    mut process_state: ResMut<ProcessState>,
+) -> Result {
+    for (interaction, button, mut bg, children) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                let Some(rule) = rules.rules.get_mut(button.0) else {
+                    continue;
+                };
+                rule.enabled = !rule.enabled;
+                let enabled = rule.enabled;
+                if let Ok(mut text) = text_query.get_mut(children[0]) {
+                    text.0 = if enabled { "on" } else { "off" }.to_string();
+                }
+
+                if let Err(e) = rules.save() {
+                    process_state
+                        .toast_message
+                        .push(format!("Failed to save {}: {}", RULES_FILE, e));
+                }
+                for index in 0..data.state.lines.len() {
+                    rules.apply(index, &mut data.state);
+                }
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
+            }
+        }
+    }
+    Ok(())
+}
+
+// save the arguments and tell the user when it fails
+fn save_ffmpeg_arg(ffmpeg_arg: &FfmpegArg, process_state: &mut ProcessState) {
+    if let Err(e) = ffmpeg_arg.save() {
+        process_state
+            .toast_message
+            .push(format!("Failed to save {}: {}", FFMPEG_ARG_FILE, e));
+    }
+}
+
+// type into the focused argument box
+pub fn text_input_system(
+    mut keyboard_events: EventReader<KeyboardInput>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut focus: ResMut<InputFocus>,
+    field_query: Query<(&ArgField, &Children)>,
+    input_query: Query<&Children, With<ArgInput>>,
+    mut text_query: Query<&mut Text>,
+    mut ffmpeg_arg: ResMut<FfmpegArg>,
+    mut process_state: ResMut<ProcessState>,
+) {
+    let Some(focused) = focus.0 else {
+        keyboard_events.clear();
+        return;
+    };
+    // the first child of the box is the text
+    let (field, children) = if let Ok((field, children)) = field_query.get(focused) {
+        (Some(field), children)
+    } else if let Ok(children) = input_query.get(focused) {
+        (None, children)
+    } else {
+        keyboard_events.clear();
+        return;
+    };
+    let Ok(mut text) = text_query.get_mut(children[0]) else {
+        return;
+    };
+    // leave the shortcuts (e.g. paste) to the other systems
+    let modifier = keyboard.any_pressed([
+        KeyCode::SuperLeft,
+        KeyCode::SuperRight,
+        KeyCode::ControlLeft,
+        KeyCode::ControlRight,
+    ]);
+
+    let mut edited = false;
+    let mut submit = false;
+    for event in keyboard_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Character(c) if !modifier => {
+                text.0.push_str(c);
+                edited = true;
+            }
+            Key::Space => {
+                text.0.push(' ');
+                edited = true;
+            }
+            Key::Backspace => {
+                text.0.pop();
+                edited = true;
+            }
+            Key::Enter | Key::Escape => {
+                submit = true;
+            }
+            _ => {}
+        }
+    }
+
+    // existing rows are edited in place, new rows wait for the submit button
+    if let Some(field) = field {
+        if edited {
+            if let Some(arg) = ffmpeg_arg
+                .group_mut(field.group)
+                .and_then(|args| args.get_mut(field.row))
+            {
+                match field.kind {
+                    ArgFieldKind::Key => arg.key = text.0.clone(),
+                    ArgFieldKind::Value => arg.value = text.0.clone(),
+                }
+            }
+        }
+        if submit {
+            save_ffmpeg_arg(&ffmpeg_arg, &mut process_state);
+        }
+    }
+    if submit {
+        focus.0 = None;
+    }
+}
+
+// add a new argument from the input boxes
+pub fn arg_submit_interaction(
+    mut interaction_query: Query<
+        (&Interaction, &ArgSubmitButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    input_query: Query<(&ArgInput, &Children)>,
+    mut text_query: Query<&mut Text>,
+    mut ffmpeg_arg: ResMut<FfmpegArg>,
+    mut process_state: ResMut<ProcessState>,
+) -> Result {
+    for (interaction, button, mut bg) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                let mut arg = ArgKeyValue::new("", "");
+                for (input, children) in input_query.iter() {
+                    if input.group != button.0 {
+                        continue;
+                    }
+                    let Ok(text) = text_query.get(children[0]) else {
+                        continue;
+                    };
+                    match input.kind {
+                        ArgFieldKind::Key => arg.key = text.0.trim().to_string(),
+                        ArgFieldKind::Value => arg.value = text.0.trim().to_string(),
+                    }
+                }
+                if arg.key.is_empty() {
+                    process_state
+                        .toast_message
+                        .push("The argument key is empty".to_string());
+                    continue;
+                }
+                let Some(args) = ffmpeg_arg.group_mut(button.0) else {
+                    continue;
+                };
+                args.push(arg);
+                ffmpeg_arg.changed = true;
+                save_ffmpeg_arg(&ffmpeg_arg, &mut process_state);
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::WHITE.with_alpha(0.1));
+            }
+        }
+    }
+    Ok(())
+}
+
+// move up, move down or remove an argument
+pub fn arg_row_interaction(
+    mut interaction_query: Query<
+        (&Interaction, &ArgRowButton, &mut BackgroundColor),
+        Changed<Interaction>,
+    >,
+    mut ffmpeg_arg: ResMut<FfmpegArg>,
+    mut process_state: ResMut<ProcessState>,
+) -> Result {
+    for (interaction, button, mut bg) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                let Some(args) = ffmpeg_arg.group_mut(button.group) else {
+                    continue;
+                };
+                let row = button.row;
+                if row >= args.len() {
+                    continue;
+                }
+                match button.action {
+                    ArgRowAction::Up if row > 0 => args.swap(row, row - 1),
+                    ArgRowAction::Down if row + 1 < args.len() => args.swap(row, row + 1),
+                    ArgRowAction::Remove => {
+                        args.remove(row);
+                    }
+                    _ => continue,
+                }
+                ffmpeg_arg.changed = true;
+                save_ffmpeg_arg(&ffmpeg_arg, &mut process_state);
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
+            }
+        }
+    }
+    Ok(())
+}
+
+// save the edited arguments when leaving the Setting state
+pub fn exit_setting(ffmpeg_arg: Res<FfmpegArg>, mut process_state: ResMut<ProcessState>) {
+    save_ffmpeg_arg(&ffmpeg_arg, &mut process_state);
+}
@@ -1,3 +1,4 @@
+use crate::systems::{clear_lines, import_lines, register_watch_folders};
 use crate::utility::analyze_ffprobe_command;
 use crate::{TOKIO_RT, define::*};
 use arboard::Clipboard;
@@ -8,9 +9,13 @@ use tokio::sync::mpsc;
 pub fn shortcuts(
     keyboard: Res<ButtonInput<KeyCode>>,
     mut data: ResMut<PathDatas>,
+    mut queue: ResMut<TaskQueue>,
+    mut logs: ResMut<TaskLogs>,
     process_menu: Res<ProcessMenu>,
     mut process_state: ResMut<ProcessState>,
     ffmpeg_args: Res<FfmpegArg>,
+    mut config: ResMut<AppConfig>,
+    watch: Res<WatchState>,
 ) -> Result {
     let mut clipboard = Clipboard::new()?;
 
@@ -24,89 +29,87 @@ pub fn shortcuts(
             return Ok(());
         };
 
-        // replace all lines when type is ONCE
-        let mut lines = match process_menu.import_type {
+        // Cmd+Shift+V registers the pasted folders as watch folders
+        // e.g. `/ingest | *.mov,*.mp4 | recursive | 30s | queue=h264`
+        if keyboard.pressed(KeyCode::ShiftLeft) {
+            register_watch_folders(
+                &contents,
+                &mut config,
+                &watch,
+                &ffmpeg_args,
+                &mut process_state,
+            );
+            return Ok(());
+        }
+
+        // replace all lines when type is ONCE, append lines when type is SEQUENCE
+        match process_menu.import_type {
             MenuImportButton::Lock => {
                 return Ok(()); // do nothing when type is LOCK
             }
             MenuImportButton::Once => {
-                vec![]
-            }
-            MenuImportButton::Sequence => data.state.lines.clone(),
-        };
-
-        // append lines when type is SEQUENCE
-        for (_index, line) in contents.lines().enumerate() {
-            if !line.is_empty() && !lines.contains(&line.to_string()) {
-                lines.push(line.to_string());
+                if let Err(e) = clear_lines(&mut data, &mut queue, &mut logs) {
+                    process_state.toast_message.push(e);
+                    return Ok(());
+                }
             }
+            MenuImportButton::Sequence => {}
         }
-        if data.state.lines == lines {
-            return Ok(());
-        } else {
-            data.state.lines = lines;
-            data.state.status = vec![TaskStatus::Waiting; data.state.lines.len()];
-            let args = ffmpeg_args.analyze.clone();
-            // start analyze duration
-            analyze_duration(
-                data.state.lines.clone(),
-                process_state.progress_tx.clone(),
-                args,
-                process_state.toast_tx.clone(),
-            );
-            data.changed = true;
-            //info!("storage in PathDatas");
-            process_state
-                .toast_message
-                .push("Failed to get clipboard text".to_string());
-        }
+        let lines = contents.lines().map(str::to_string);
+        let added = import_lines(&mut data, lines, &process_state, &ffmpeg_args.analyze);
+        //info!("storage in PathDatas");
+        process_state
+            .toast_message
+            .push(format!("{} files imported", added.len()));
     }
     Ok(())
 }
 
-fn analyze_duration(
-    lines: Vec<String>,
+// each line with its index in FilesState, the messages carry the path, so the results of
+// lines that were removed or replaced meanwhile are dropped by progress_receiver
+pub fn analyze_media(
+    lines: Vec<(usize, String)>,
     tx: mpsc::Sender<ProgressInfo>,
     args: Vec<ArgKeyValue>,
     toast_tx: mpsc::Sender<String>,
 ) {
     std::thread::spawn(move || {
-        for (index, line) in lines.iter().enumerate() {
+        for (index, line) in lines {
             TOKIO_RT.block_on(async {
-                let mut cmd = analyze_ffprobe_command(line.to_string(), &args);
-                let total_secs = match cmd.output().await {
+                let mut cmd = analyze_ffprobe_command(line.clone(), &args);
+                let info = match cmd.output().await {
                     Ok(output) => {
                         if output.status.success() {
                             let stdout = String::from_utf8_lossy(&output.stdout);
-                            if let Some(duration) = stdout.lines().next() {
-                                // parse f64 from str
-                                duration.parse::<f64>().unwrap_or_else(|_| {
-                                    //info!("Failed to parse duration from output: {}", duration);
-
-                                    0.0 // default to 0.0 if parsing fails
-                                })
-                            } else {
-                                0.0 // default to 0 if no duration found
-                            }
+                            // parse the json output of ffprobe
+                            MediaInfo::from_ffprobe(&stdout).unwrap_or_else(|e| {
+                                //info!("Failed to parse ffprobe output: {}", e);
+                                let _ = toast_tx.try_send(format!("ffprobe parse error: {}", e));
+                                MediaInfo::default()
+                            })
                         } else {
                             // info!(
                             //     "ffprobe command failed: {}",
                             //     String::from_utf8_lossy(&output.stderr)
                             // );
 
-                            0.0 // default to 0 on failure
+                            MediaInfo::default() // default to empty on failure
                         }
                     }
                     Err(e) => {
                         //info!("ffprobe command error: {}", e);
                         let msg = format!("ffprobe command error: {}", e);
                         let _ = toast_tx.send(msg).await;
-                        0.0 // default to 0 on error
+                        MediaInfo::default() // default to empty on error
                     }
                 };
-                let msg = format!("analyze duration: {} secs", total_secs);
+                let msg = format!("analyze: {} {:.0} secs", info.summary(), info.duration);
                 let _ = toast_tx.send(msg).await;
-                let _ = tx.send(ProgressInfo::total(total_secs as u64, index)).await;
+                let total = ProgressInfo::total(info.duration as u64, index);
+                let _ = tx.send(total.with_path(&line)).await;
+                let _ = tx
+                    .send(ProgressInfo::media(info, index).with_path(&line))
+                    .await;
             });
         }
     });
@@ -0,0 +1,38 @@
+use crate::define::*;
+use bevy::prelude::*;
+
+// collect ffmpeg output lines sent by the task threads
+pub fn log_receiver(mut process_state: ResMut<ProcessState>, mut logs: ResMut<TaskLogs>) {
+    while let Ok((index, line)) = process_state.log_rx.try_recv() {
+        logs.push(index, line);
+    }
+}
+
+// log button interaction
+pub fn log_interaction(
+    mut interaction_query: Query<
+        (Entity, &Interaction, &IndexOfline, &mut BackgroundColor),
+        (Changed<Interaction>, With<LogButton>),
+    >,
+    mut process_menu: ResMut<ProcessMenu>,
+) -> Result {
+    for (_entity, interaction, idx, mut bg) in interaction_query.iter_mut() {
+        match *interaction {
+            Interaction::Hovered => {
+                *bg = BackgroundColor(Color::srgb_u8(0, 84, 0));
+            }
+            Interaction::Pressed => {
+                // toggle the log window of this line
+                process_menu.log_index = if process_menu.log_index == Some(idx.0) {
+                    None
+                } else {
+                    Some(idx.0)
+                };
+            }
+            Interaction::None => {
+                *bg = BackgroundColor(Color::srgb_u8(16, 16, 16));
+            }
+        }
+    }
+    Ok(())
+}
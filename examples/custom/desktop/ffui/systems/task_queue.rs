@@ -0,0 +1,288 @@
+use crate::define::*;
+use crate::utility::replace::move_file;
+use crate::utility::task::task;
+use bevy::prelude::*;
+use std::path::{Path, PathBuf};
+
+// why the output of a line can not be written, checked before the task starts
+fn output_collision(state: &FilesState, index: usize, output: &Path) -> Option<String> {
+    if let Some(line) = state
+        .lines
+        .iter()
+        .position(|line| Path::new(line) == output)
+    {
+        return Some(format!(
+            "output would overwrite the source of line {}",
+            line + 1
+        ));
+    }
+    let other = state.outputs.iter().find(|(i, path)| {
+        **i != index
+            && path.as_path() == output
+            && matches!(
+                state.status.get(**i),
+                Some(TaskStatus::Running | TaskStatus::Done)
+            )
+    });
+    if let Some((i, _)) = other {
+        return Some(format!("output is also written by line {}", i + 1));
+    }
+    // a file of the previous run of this line can be overwritten
+    if output.exists() && state.outputs.get(&index).map(|p| p.as_path()) != Some(output) {
+        return Some(format!("output already exists: {}", output.display()));
+    }
+    None
+}
+
+// move the partial output of an interrupted run out of the way of the resumed one
+fn keep_first_part(index: usize, partial: &Path) -> Result<PathBuf, String> {
+    let dir = std::env::temp_dir().join("ffui");
+    // a part that is already kept aside stays where it is
+    if partial.parent() == Some(dir.as_path()) {
+        return Ok(partial.to_path_buf());
+    }
+    let ext = partial
+        .extension()
+        .map(|ext| format!(".{}", ext.to_string_lossy()))
+        .unwrap_or_default();
+    let first = dir.join(format!("resume-{}-{}{}", std::process::id(), index, ext));
+    std::fs::create_dir_all(&dir)
+        .map_err(|e| format!("failed to create {}: {}", dir.display(), e))?;
+    move_file(partial, &first).map_err(|e| e.to_string())?;
+    Ok(first)
+}
+
+fn create_parent_dirs<'a>(
+    paths: impl IntoIterator<Item = Option<&'a PathBuf>>,
+) -> std::io::Result<()> {
+    for dir in paths.into_iter().flatten().filter_map(|path| path.parent()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    Ok(())
+}
+
+// take jobs from the queue while there are free slots
+pub fn task_scheduler(
+    mut queue: ResMut<TaskQueue>,
+    mut data: ResMut<PathDatas>,
+    mut logs: ResMut<TaskLogs>,
+    process_state: Res<ProcessState>,
+    ffmpeg_args: Res<FfmpegArg>,
+    config: Res<AppConfig>,
+) -> Result {
+    if queue.paused || queue.jobs.is_empty() {
+        return Ok(());
+    }
+
+    let mut running = data
+        .state
+        .status
+        .iter()
+        .filter(|status| matches!(status, TaskStatus::Running))
+        .count();
+
+    // jobs whose size target needs the analyzed duration, they go back to the queue
+    let mut waiting = vec![];
+    while running < queue.max_parallel {
+        let Some(job) = queue.jobs.pop_front() else {
+            break;
+        };
+        // the line may be replaced (import once) or dequeued after it was queued
+        let Some(path) = data.state.lines.get(job.index).cloned() else {
+            continue;
+        };
+        if !matches!(data.state.status.get(job.index), Some(TaskStatus::Queued)) {
+            continue;
+        }
+        // headless mode, the watch folders and Start right after an import queue the
+        // files before ffprobe is done
+        let needs_media = ffmpeg_args
+            .preset(&job.preset)
+            .is_some_and(|preset| preset.target.is_some());
+        if needs_media && !data.state.media.contains_key(&job.index) {
+            waiting.push(job);
+            continue;
+        }
+        // forget the output of the previous run
+        data.state.errors.remove(&job.index);
+        data.state.quality.remove(&job.index);
+        logs.0.remove(&job.index);
+
+        // the preset may be renamed or removed after the line was queued
+        let Some(preset) = ffmpeg_args.preset(&job.preset).cloned() else {
+            data.state.status[job.index] = TaskStatus::Failed;
+            data.state
+                .errors
+                .insert(job.index, format!("preset not found: {}", job.preset));
+            continue;
+        };
+        // the bitrate of a size target follows the analyzed duration (of the trimmed part)
+        let edit = data
+            .state
+            .edits
+            .get(&job.index)
+            .cloned()
+            .unwrap_or_default();
+        let media = data.state.media.get(&job.index).map(|info| {
+            let duration = edit.duration(info.duration);
+            let part = if info.duration > 0.0 {
+                duration / info.duration
+            } else {
+                1.0
+            };
+            MediaInfo {
+                duration,
+                size: (info.size as f64 * part) as u64,
+                ..info.clone()
+            }
+        });
+        // a resumed job reads the source from where the readable part of its partial output
+        // ends, the bitrate of a size target stays the one of the whole part
+        // the first pass log of a two-pass encode and the parts of a segmented output can not
+        // be continued, those jobs start again
+        let resume = data
+            .state
+            .partials
+            .get(&job.index)
+            .filter(|partial| job.resume && partial.preset == job.preset && partial.secs > 0.0)
+            .filter(|partial| partial.path.exists() && !preset.two_pass && edit.segment.is_none())
+            .cloned();
+        let encoded = match &resume {
+            Some(partial) => FileEdit {
+                start: Some(edit.start.unwrap_or(0.0) + partial.secs),
+                ..edit.clone()
+            },
+            None => edit.clone(),
+        };
+        let preset = match preset.encode_args(media.as_ref()) {
+            // the arguments of the edit panel come first, so its crop is the first filter
+            Ok(args) => Preset {
+                args: [encoded.args(hw_frames(&args)), args].concat(),
+                ..preset
+            },
+            Err(e) => {
+                data.state.status[job.index] = TaskStatus::Failed;
+                data.state.errors.insert(job.index, e);
+                continue;
+            }
+        };
+        if preset.two_pass && preset.hardware {
+            data.state.status[job.index] = TaskStatus::Failed;
+            data.state.errors.insert(
+                job.index,
+                format!("{}: two-pass needs a software encoder", preset.name),
+            );
+            continue;
+        }
+        let output = match config.output.render(&path, &preset) {
+            Some(output) => output,
+            None => {
+                data.state.status[job.index] = TaskStatus::Failed;
+                data.state
+                    .errors
+                    .insert(job.index, format!("invalid output path for {}", path));
+                continue;
+            }
+        };
+        // ffmpeg writes the parts of a segmented output, the first one stands for the others
+        let pattern = edit.pattern(&output);
+        let output = pattern
+            .as_ref()
+            .map_or(output, |pattern| segment_part(pattern, 0));
+        if let Some(error) = output_collision(&data.state, job.index, &output) {
+            data.state.status[job.index] = TaskStatus::Failed;
+            data.state.errors.insert(job.index, error);
+            continue;
+        }
+        // the parts of a segmented output are written in place
+        let staging = config
+            .output
+            .staging_path(job.index, &output)
+            .filter(|_| pattern.is_none());
+        if let Err(e) = create_parent_dirs([Some(&output), staging.as_ref()]) {
+            data.state.status[job.index] = TaskStatus::Failed;
+            data.state
+                .errors
+                .insert(job.index, format!("failed to create output folder: {}", e));
+            continue;
+        }
+        let written = staging.clone().unwrap_or_else(|| output.clone());
+        // the part written before the interruption is kept aside, ffmpeg writes the rest
+        let first = match &resume {
+            Some(partial) => match keep_first_part(job.index, &partial.path) {
+                Ok(first) => Some(first),
+                Err(e) => {
+                    data.state.status[job.index] = TaskStatus::Failed;
+                    data.state.errors.insert(job.index, e);
+                    continue;
+                }
+            },
+            None => None,
+        };
+        let old = data.state.partials.remove(&job.index);
+        if let (Some(first), Some(partial)) = (&first, resume.clone()) {
+            // the first part stands for the partial output until the resumed task is done
+            data.state.partials.insert(
+                job.index,
+                PartialOutput {
+                    path: first.clone(),
+                    ..partial
+                },
+            );
+        } else {
+            // the part kept aside by an earlier resume is not needed when starting again
+            if let Some(old) = old
+                && old.path != written
+                && old.path != output
+            {
+                let _ = std::fs::remove_file(&old.path);
+            }
+            if !preset.two_pass && pattern.is_none() {
+                data.state.partials.insert(
+                    job.index,
+                    PartialOutput {
+                        preset: job.preset.clone(),
+                        path: written,
+                        secs: 0.0,
+                    },
+                );
+            }
+        }
+        data.state.outputs.insert(job.index, output.clone());
+        let output = match pattern {
+            Some(pattern) => {
+                data.state.segments.insert(job.index, pattern.clone());
+                pattern
+            }
+            None => {
+                data.state.segments.remove(&job.index);
+                output
+            }
+        };
+        if let Some(statistics) = data.state.progress.get_mut(&job.index) {
+            statistics.pass = None;
+            // the progress of a trimmed file runs to the length of the part
+            // a resumed one to the rest after the partial output
+            if let Some(media) = &media {
+                let done = resume.as_ref().map_or(0.0, |partial| partial.secs);
+                statistics.total = (media.duration - done).max(0.0) as u64;
+            }
+        }
+        data.state.status[job.index] = TaskStatus::Running;
+        task(
+            job.index,
+            &process_state,
+            path,
+            output,
+            staging,
+            first,
+            preset,
+        );
+        running += 1;
+    }
+    // in their old order, before the jobs that were not reached
+    for job in waiting.into_iter().rev() {
+        queue.jobs.push_front(job);
+    }
+    Ok(())
+}
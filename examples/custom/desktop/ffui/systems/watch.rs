@@ -0,0 +1,117 @@
+use crate::define::*;
+use crate::systems::import_lines;
+use bevy::prelude::*;
+use std::path::Path;
+
+// import the files of the watch folders, queue them when the folder has a preset
+pub fn watch_receiver(
+    mut watch: ResMut<WatchState>,
+    mut data: ResMut<PathDatas>,
+    mut queue: ResMut<TaskQueue>,
+    process_menu: Res<ProcessMenu>,
+    mut process_state: ResMut<ProcessState>,
+    ffmpeg_args: Res<FfmpegArg>,
+) {
+    // the files wait in the channel while the list is locked
+    if matches!(process_menu.import_type, MenuImportButton::Lock) {
+        return;
+    }
+    while let Ok(file) = watch.file_rx.try_recv() {
+        // the output of a task may be written into a watch folder
+        let path = Path::new(&file.path);
+        let written = data.state.outputs.values().any(|output| output == path)
+            || data
+                .state
+                .segments
+                .values()
+                .any(|pattern| is_segment_part(pattern, path))
+            || data
+                .state
+                .replaced
+                .values()
+                .any(|record| record.target == path);
+        if written {
+            continue;
+        }
+        let added = import_lines(
+            &mut data,
+            [file.path.clone()],
+            &process_state,
+            &ffmpeg_args.analyze,
+        );
+        let Some(preset) = file.auto_queue.filter(|_| !added.is_empty()) else {
+            continue;
+        };
+        if ffmpeg_args.preset(&preset).is_none() {
+            process_state
+                .toast_message
+                .push(format!("Unknown preset {} for {}", preset, file.path));
+            continue;
+        }
+        for index in added {
+            data.state.status[index] = TaskStatus::Queued;
+            queue.push(index, preset.clone());
+        }
+    }
+}
+
+// register the folders of the clipboard text as watch folders and save the config
+// each line is a folder with its settings (see WatchFolder::parse), a folder that is already
+// watched takes the settings of the line
+pub fn register_watch_folders(
+    contents: &str,
+    config: &mut AppConfig,
+    watch: &WatchState,
+    ffmpeg_args: &FfmpegArg,
+    process_state: &mut ProcessState,
+) {
+    let (mut added, mut updated) = (0, 0);
+    for line in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        let folder = match WatchFolder::parse(line) {
+            Ok(folder) => folder,
+            Err(e) => {
+                process_state.toast_message.push(e);
+                continue;
+            }
+        };
+        if !Path::new(&folder.path).is_dir() {
+            continue;
+        }
+        if let Some(preset) = &folder.auto_queue
+            && ffmpeg_args.preset(preset).is_none()
+        {
+            process_state
+                .toast_message
+                .push(format!("{}: preset not found: {}", folder.path, preset));
+            continue;
+        }
+        let _ = watch.folder_tx.send(folder.clone());
+        match config.watch.iter_mut().find(|old| old.path == folder.path) {
+            Some(old) => {
+                *old = folder;
+                updated += 1;
+            }
+            None => {
+                config.watch.push(folder);
+                added += 1;
+            }
+        }
+    }
+    if added + updated == 0 {
+        process_state
+            .toast_message
+            .push("No new folder to watch".to_string());
+        return;
+    }
+    process_state.toast_message.push(format!(
+        "Watching {} more folders, {} updated",
+        added, updated
+    ));
+    if let Err(e) = config.save() {
+        process_state.toast_message.push(e);
+    }
+}
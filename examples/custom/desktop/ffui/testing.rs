@@ -0,0 +1,60 @@
+// fixtures shared by the test modules
+use crate::define::{ArgKeyValue, CropRect, FileEdit, MediaInfo};
+use std::sync::{Mutex, MutexGuard};
+
+// `ffprobe -print_format json -show_format -show_streams` of a 93 minute movie
+pub const MOVIE: &str = r#"{
+    "streams": [
+        {"codec_type": "video", "codec_name": "h264", "width": 1920, "height": 1080,
+         "avg_frame_rate": "30/1", "pix_fmt": "yuv420p"},
+        {"codec_type": "audio", "codec_name": "aac", "bit_rate": "128000",
+         "channels": 2, "sample_rate": "48000"}
+    ],
+    "format": {"format_name": "mov,mp4,m4a,3gp,3g2,mj2", "duration": "5600.000000",
+               "size": "2800000000", "bit_rate": "4000000"}
+}"#;
+
+pub fn movie() -> MediaInfo {
+    MediaInfo::from_ffprobe(MOVIE).unwrap()
+}
+
+// trimmed, cropped and segmented
+pub fn full_edit() -> FileEdit {
+    FileEdit {
+        start: Some(12.5),
+        end: Some(70.0),
+        crop: Some(CropRect {
+            x: 100,
+            y: 40,
+            width: 1280,
+            height: 720,
+        }),
+        segment: Some(20),
+    }
+}
+
+// the arguments as (key, value), so they can be compared with a literal
+pub fn pairs(args: &[ArgKeyValue]) -> Vec<(&str, &str)> {
+    args.iter()
+        .map(|arg| (arg.key.as_str(), arg.value.as_str()))
+        .collect()
+}
+
+// the environment is shared by the tests of this process
+static FAKE_ENV: Mutex<()> = Mutex::new(());
+
+// point ffmpeg_bin at scripts/fake_ffmpeg.sh, until the guard is dropped
+pub fn fake_ffmpeg(hwaccels: &str, encoders: &str) -> MutexGuard<'static, ()> {
+    let guard = FAKE_ENV.lock().unwrap_or_else(|e| e.into_inner());
+    let script = concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/examples/custom/desktop/ffui/scripts/fake_ffmpeg.sh"
+    );
+    // SAFETY: the variables are only touched while FAKE_ENV is locked
+    unsafe {
+        std::env::set_var("FFUI_FFMPEG", script);
+        std::env::set_var("FAKE_HWACCELS", hwaccels);
+        std::env::set_var("FAKE_ENCODERS", encoders);
+    }
+    guard
+}
@@ -1,13 +1,114 @@
 use crate::ui::*;
 use bevy::input_focus::InputFocus;
 
-pub fn enter_monitor(mut commands: Commands, process_state: Res<ProcessState>) {
+pub fn enter_monitor(
+    mut commands: Commands,
+    process_state: Res<ProcessState>,
+    filter: Res<ListFilter>,
+    selection: Res<Selection>,
+    font: Res<FontHandle>,
+) {
     // ui container
     let Some(layout_id) = process_state.layout else {
         error!("ProcessState layout is not set");
         return;
     };
 
+    // filter bar: search on the path, status filter and sort column
+    let filter_bar_id = commands
+        .spawn((
+            StateScoped(AppState::Monitor),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(30.0),
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(5.0),
+                padding: UiRect::horizontal(Val::Px(10.0)),
+                ..default()
+            },
+        ))
+        .with_children(|p| {
+            p.spawn((
+                SearchInput,
+                Node {
+                    width: Val::Px(300.0),
+                    height: Val::Percent(100.0),
+                    padding: UiRect::all(Val::Px(5.0)),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                BorderRadius::all(Val::Px(5.0)),
+                BackgroundColor(Color::WHITE.with_alpha(0.1)),
+                children![(
+                    Text::new(filter.search.clone()),
+                    TextFont {
+                        font: font.0.clone(),
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                )],
+            ))
+            .observe(focus_on_click);
+            p.spawn(ui_menu_button(
+                MenuFilterButton(filter.status),
+                font.0.clone(),
+            ));
+            p.spawn(ui_menu_button(MenuSortButton(filter.sort), font.0.clone()));
+            p.spawn(ui_menu_button(
+                MenuOrderButton {
+                    descending: filter.descending,
+                },
+                font.0.clone(),
+            ));
+        })
+        .id();
+    commands.entity(layout_id).add_child(filter_bar_id);
+
+    // selection bar: the actions on the selected lines
+    let selection_bar_id = commands
+        .spawn((
+            StateScoped(AppState::Monitor),
+            Node {
+                width: Val::Percent(100.0),
+                height: Val::Px(30.0),
+                flex_direction: FlexDirection::Row,
+                column_gap: Val::Px(5.0),
+                padding: UiRect::horizontal(Val::Px(10.0)),
+                align_items: AlignItems::Center,
+                ..default()
+            },
+        ))
+        .with_children(|p| {
+            p.spawn((
+                SelectionText,
+                Node {
+                    width: Val::Px(100.0),
+                    ..default()
+                },
+                Text::new(format!("{} selected", selection.lines.len())),
+                TextFont {
+                    font: font.0.clone(),
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            ));
+            for action in [
+                BulkAction::Start,
+                BulkAction::Cancel,
+                BulkAction::Replace,
+                BulkAction::Remove,
+                BulkAction::Snapshot,
+                BulkAction::Analyze,
+                BulkAction::Retry,
+            ] {
+                p.spawn(ui_menu_button(MenuBulkButton(action), font.0.clone()));
+            }
+        })
+        .id();
+    commands.entity(layout_id).add_child(selection_bar_id);
+
     let container_id = commands
         .spawn((
             StateScoped(AppState::Monitor),
@@ -26,6 +127,15 @@ pub fn enter_monitor(mut commands: Commands, process_state: Res<ProcessState>) {
                 align_self: AlignSelf::Stretch,
                 ..default()
             },
+            children![(
+                LinesContent,
+                Node {
+                    width: Val::Percent(100.0),
+                    flex_shrink: 0.0,
+                    position_type: PositionType::Relative,
+                    ..default()
+                },
+            )],
         ))
         .id();
     commands.entity(layout_id).add_child(container_id);
@@ -35,6 +145,7 @@ pub fn enter_setting(
     mut commands: Commands,
     process_state: Res<ProcessState>,
     ffmpeg_arg: Res<FfmpegArg>,
+    rules: Res<ProfileRules>,
     font: Res<FontHandle>,
     mut focus: ResMut<InputFocus>,
 ) {
@@ -47,6 +158,55 @@ pub fn enter_setting(
     // reset focus
     focus.0 = None;
 
+    spawn_setting(
+        &mut commands,
+        layout_id,
+        &ffmpeg_arg,
+        &rules,
+        font.0.clone(),
+    );
+}
+
+// rebuild the setting panel after arguments are added, removed or reordered
+pub fn refresh_setting(
+    mut commands: Commands,
+    process_state: Res<ProcessState>,
+    mut ffmpeg_arg: ResMut<FfmpegArg>,
+    rules: Res<ProfileRules>,
+    font: Res<FontHandle>,
+    container_query: Query<Entity, With<SettingContainer>>,
+    mut focus: ResMut<InputFocus>,
+) {
+    // no changes, just return
+    if !ffmpeg_arg.changed {
+        return;
+    }
+    ffmpeg_arg.changed = false;
+
+    let Some(layout_id) = process_state.layout else {
+        error!("ProcessState layout is not set");
+        return;
+    };
+    for entity in container_query.iter() {
+        commands.entity(entity).despawn();
+    }
+    focus.0 = None;
+    spawn_setting(
+        &mut commands,
+        layout_id,
+        &ffmpeg_arg,
+        &rules,
+        font.0.clone(),
+    );
+}
+
+fn spawn_setting(
+    commands: &mut Commands,
+    layout_id: Entity,
+    ffmpeg_arg: &FfmpegArg,
+    rules: &ProfileRules,
+    font: Handle<Font>,
+) {
     let setting_id = commands
         .spawn((
             StateScoped(AppState::Setting),
@@ -68,34 +228,49 @@ pub fn enter_setting(
         ))
         .id();
 
-    let ffmpeg_hw_id = commands
-        .spawn(
-            // ffmpeg hw arguments
-            (
+    // one column for each preset, titled with its name
+    for (index, preset) in ffmpeg_arg.presets.iter().enumerate() {
+        let preset_id = commands
+            .spawn((
                 Node {
-                    //size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                     width: Val::Percent(100.0),
                     height: Val::Percent(100.0),
-                    //height: Val::Px(300.0), // or Val::Percent(50.0)
                     position_type: PositionType::Relative,
-                    //margin: UiRect::all(Val::Px(10.0)),
                     padding: UiRect::all(Val::Px(10.0)),
                     flex_direction: FlexDirection::Column,
                     row_gap: Val::Px(5.0),
-                    //align_self: AlignSelf::Stretch,
-                    justify_content: JustifyContent::SpaceBetween,
+                    align_self: AlignSelf::Stretch,
                     border: UiRect::all(Val::Px(1.0)),
                     ..default()
                 },
                 BorderColor(Color::BLACK.with_alpha(0.5)),
-                arguments_panel(&ffmpeg_arg.hw_convert, font.0.clone(), 0),
-            ),
-        )
-        .id();
-    commands.entity(setting_id).add_child(ffmpeg_hw_id);
-
-    // ffmpeg sf arguments
-    let ffmpeg_sf_id = commands
+                children![
+                    (
+                        Text::new(preset_title(preset)),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 14.0,
+                            ..default()
+                        },
+                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                    ),
+                    (
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(5.0),
+                            ..default()
+                        },
+                        arguments_panel(&preset.args, font.clone(), ArgGroup::Preset(index)),
+                    )
+                ],
+            ))
+            .id();
+        commands.entity(setting_id).add_child(preset_id);
+    }
+    // preview arguments
+    let preivew_id = commands
         .spawn((
             Node {
                 //size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -106,19 +281,19 @@ pub fn enter_setting(
                 //margin: UiRect::all(Val::Px(10.0)),
                 padding: UiRect::all(Val::Px(10.0)),
                 flex_direction: FlexDirection::Column,
-                border: UiRect::all(Val::Px(1.0)),
                 row_gap: Val::Px(5.0),
                 align_self: AlignSelf::Stretch,
+                border: UiRect::all(Val::Px(1.0)),
                 ..default()
             },
             BorderColor(Color::BLACK.with_alpha(0.5)),
-            arguments_panel(&ffmpeg_arg.sf_convert, font.0.clone(), 1),
-            //BackgroundColor(Color::srgb_u8(0, 128, 0)),
+            arguments_panel(&ffmpeg_arg.snapshot, font.clone(), ArgGroup::Snapshot),
+            //BackgroundColor(Color::srgb_u8(0, 0, 128)),
         ))
         .id();
-    commands.entity(setting_id).add_child(ffmpeg_sf_id);
-    // preview arguments
-    let preivew_id = commands
+    commands.entity(setting_id).add_child(preivew_id);
+    // analyze arguments
+    let analyze_id = commands
         .spawn((
             Node {
                 //size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
@@ -135,21 +310,17 @@ pub fn enter_setting(
                 ..default()
             },
             BorderColor(Color::BLACK.with_alpha(0.5)),
-            arguments_panel(&ffmpeg_arg.snapshot, font.0.clone(), 2),
-            //BackgroundColor(Color::srgb_u8(0, 0, 128)),
+            arguments_panel(&ffmpeg_arg.analyze, font.clone(), ArgGroup::Analyze),
         ))
         .id();
-    commands.entity(setting_id).add_child(preivew_id);
-    // analyze arguments
-    let analyze_id = commands
+    commands.entity(setting_id).add_child(analyze_id);
+    // profile rules
+    let rules_id = commands
         .spawn((
             Node {
-                //size: Size::new(Val::Percent(100.0), Val::Percent(100.0)),
                 width: Val::Percent(100.0),
                 height: Val::Percent(100.0),
-                //height: Val::Px(300.0), // or Val::Percent(50.0)
                 position_type: PositionType::Relative,
-                //margin: UiRect::all(Val::Px(10.0)),
                 padding: UiRect::all(Val::Px(10.0)),
                 flex_direction: FlexDirection::Column,
                 row_gap: Val::Px(5.0),
@@ -158,10 +329,10 @@ pub fn enter_setting(
                 ..default()
             },
             BorderColor(Color::BLACK.with_alpha(0.5)),
-            arguments_panel(&ffmpeg_arg.analyze, font.0.clone(), 3),
+            rules_panel(rules, font.clone()),
         ))
         .id();
-    commands.entity(setting_id).add_child(analyze_id);
+    commands.entity(setting_id).add_child(rules_id);
 
     commands.entity(layout_id).add_child(setting_id);
 }
@@ -185,3 +356,15 @@ pub fn focus_system(
         }
     }
 }
+
+// name, extension and encode mode of a preset
+fn preset_title(preset: &Preset) -> String {
+    let mut title = format!("{} (.{})", preset.name, preset.ext);
+    if let Some(target) = preset.target {
+        title.push_str(&format!(" target {}", target));
+    }
+    if preset.two_pass {
+        title.push_str(" two-pass");
+    }
+    title
+}
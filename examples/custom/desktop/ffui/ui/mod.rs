@@ -5,17 +5,20 @@ pub mod app_state;
 
 pub mod refresh;
 pub mod setup;
+pub mod ui_rules;
 pub mod ui_text_input;
 
 pub use app_state::*;
 
 pub use refresh::*;
+pub use ui_rules::*;
 pub use ui_text_input::*;
 
 use accesskit::{Node as Accessible, Role};
 use bevy::a11y::AccessibilityNode;
+use bevy::ecs::spawn::SpawnIter;
 
-pub fn ui_task_button(index: usize, font: Handle<Font>) -> impl Bundle {
+pub fn ui_task_button(index: usize, preset: String, font: Handle<Font>) -> impl Bundle {
     (
         AccessibilityNode(Accessible::new(Role::ListItem)),
         Pickable {
@@ -25,10 +28,11 @@ pub fn ui_task_button(index: usize, font: Handle<Font>) -> impl Bundle {
         Button,
         IndexOfline(index),
         TaskButton,
-        TaskButtonType(true),
+        TaskButtonType(preset.clone()),
         Node {
-            width: Val::Px(60.),
+            min_width: Val::Px(40.),
             height: Val::Px(30.0),
+            padding: UiRect::horizontal(Val::Px(8.0)),
             border: UiRect::all(Val::Px(1.0)),
             // horizontally center child text
             justify_content: JustifyContent::Center,
@@ -40,7 +44,7 @@ pub fn ui_task_button(index: usize, font: Handle<Font>) -> impl Bundle {
         //BorderColor(Color::WHITE.with_alpha(0.2)),
         BackgroundColor(Color::srgb_u8(0, 0, 0)),
         children![(
-            Text::new("hw"),
+            Text::new(preset),
             TextFont {
                 font,
                 font_size: 12.0,
@@ -52,12 +56,32 @@ pub fn ui_task_button(index: usize, font: Handle<Font>) -> impl Bundle {
     )
 }
 
-pub fn ui_task_ex_button(index: usize, font: Handle<Font>) -> impl Bundle {
+// the task buttons of a row, generated from the preset list
+pub fn ui_task_buttons(index: usize, presets: Vec<String>, font: Handle<Font>) -> impl Bundle {
+    (
+        Node {
+            height: Val::Px(30.0),
+            flex_direction: FlexDirection::Row,
+            column_gap: Val::Px(5.0),
+            ..default()
+        },
+        Pickable {
+            should_block_lower: false,
+            ..default()
+        },
+        Children::spawn(SpawnIter(
+            presets
+                .into_iter()
+                .map(move |preset| ui_task_button(index, preset, font.clone())),
+        )),
+    )
+}
+
+pub fn ui_replace_button(index: usize, font: Handle<Font>) -> impl Bundle {
     (
         Button,
         IndexOfline(index),
-        TaskButton,
-        TaskButtonType(false),
+        ReplaceButton,
         Node {
             width: Val::Px(60.),
             height: Val::Px(30.0),
@@ -72,7 +96,7 @@ pub fn ui_task_ex_button(index: usize, font: Handle<Font>) -> impl Bundle {
         //BorderColor(Color::WHITE.with_alpha(0.2)),
         BackgroundColor(Color::srgb_u8(0, 0, 0)),
         children![(
-            Text::new("sf"),
+            Text::new("move"),
             TextFont {
                 font,
                 font_size: 12.0,
@@ -84,13 +108,13 @@ pub fn ui_task_ex_button(index: usize, font: Handle<Font>) -> impl Bundle {
     )
 }
 
-pub fn ui_replace_button(index: usize, font: Handle<Font>) -> impl Bundle {
+pub fn ui_undo_button(index: usize, font: Handle<Font>) -> impl Bundle {
     (
         Button,
         IndexOfline(index),
-        ReplaceButton,
+        UndoReplaceButton,
         Node {
-            width: Val::Px(60.),
+            width: Val::Px(40.),
             height: Val::Px(30.0),
             border: UiRect::all(Val::Px(1.0)),
             // horizontally center child text
@@ -103,7 +127,7 @@ pub fn ui_replace_button(index: usize, font: Handle<Font>) -> impl Bundle {
         //BorderColor(Color::WHITE.with_alpha(0.2)),
         BackgroundColor(Color::srgb_u8(0, 0, 0)),
         children![(
-            Text::new("move"),
+            Text::new("undo"),
             TextFont {
                 font,
                 font_size: 12.0,
@@ -177,6 +201,242 @@ pub fn ui_open_button(index: usize, font: Handle<Font>) -> impl Bundle {
     )
 }
 
+pub fn ui_reveal_button(index: usize, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        IndexOfline(index),
+        RevealButton,
+        Node {
+            width: Val::Px(60.),
+            height: Val::Px(30.0),
+            border: UiRect::all(Val::Px(1.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        //BorderColor(Color::WHITE.with_alpha(0.2)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new("reveal"),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            //TextShadow::default(),
+        )],
+    )
+}
+
+pub fn ui_copy_button(index: usize, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        IndexOfline(index),
+        CopyPathButton,
+        Node {
+            width: Val::Px(40.),
+            height: Val::Px(30.0),
+            border: UiRect::all(Val::Px(1.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        //BorderColor(Color::WHITE.with_alpha(0.2)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new("copy"),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            //TextShadow::default(),
+        )],
+    )
+}
+
+pub fn ui_compare_button(index: usize, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        IndexOfline(index),
+        CompareButton,
+        Node {
+            width: Val::Px(40.),
+            height: Val::Px(30.0),
+            border: UiRect::all(Val::Px(1.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new("A|B"),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        )],
+    )
+}
+
+// a button of the comparison window toolbar
+pub fn ui_compare_control(action: CompareAction, label: &str, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        CompareControl(action),
+        Node {
+            min_width: Val::Px(40.),
+            height: Val::Px(26.0),
+            padding: UiRect::horizontal(Val::Px(6.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new(label),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        )],
+    )
+}
+
+pub fn ui_edit_button(index: usize, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        IndexOfline(index),
+        EditButton,
+        Node {
+            width: Val::Px(40.),
+            height: Val::Px(30.0),
+            border: UiRect::all(Val::Px(1.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new("edit"),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        )],
+    )
+}
+
+// a button of the edit window toolbar
+pub fn ui_edit_control(action: EditAction, label: &str, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        EditControl(action),
+        Node {
+            min_width: Val::Px(40.),
+            height: Val::Px(26.0),
+            padding: UiRect::horizontal(Val::Px(6.0)),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new(label),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        )],
+    )
+}
+
+pub fn ui_log_button(index: usize, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        IndexOfline(index),
+        LogButton,
+        Node {
+            width: Val::Px(40.),
+            height: Val::Px(30.0),
+            border: UiRect::all(Val::Px(1.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        //BorderColor(Color::WHITE.with_alpha(0.2)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new("log"),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            //TextShadow::default(),
+        )],
+    )
+}
+
+pub fn ui_details_button(index: usize, font: Handle<Font>) -> impl Bundle {
+    (
+        Button,
+        IndexOfline(index),
+        DetailsButton,
+        Node {
+            width: Val::Px(40.),
+            height: Val::Px(30.0),
+            border: UiRect::all(Val::Px(1.0)),
+            // horizontally center child text
+            justify_content: JustifyContent::Center,
+            // vertically center child text
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        //BorderColor(Color::WHITE.with_alpha(0.2)),
+        BackgroundColor(Color::srgb_u8(0, 0, 0)),
+        children![(
+            Text::new("info"),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            //TextShadow::default(),
+        )],
+    )
+}
+
 pub fn ui_menu_button<T: MenuButtonType + MenuButtonNext + std::fmt::Debug>(
     bt: T,
     font: Handle<Font>,
@@ -1,38 +1,14 @@
 use crate::define::*;
 use crate::ui::{
-    ui_open_button, ui_replace_button, ui_snap_button, ui_task_button, ui_task_ex_button,
+    ui_compare_button, ui_copy_button, ui_details_button, ui_edit_button, ui_log_button,
+    ui_open_button, ui_replace_button, ui_reveal_button, ui_snap_button, ui_task_buttons,
+    ui_undo_button,
 };
+use crate::utility::time::{format_duration, format_size};
 use accesskit::{Node as Accessible, Role};
 use bevy::a11y::AccessibilityNode;
 use bevy::prelude::*;
 
-// switch show and hide row
-pub fn show_hide_row(
-    data: ResMut<PathDatas>,
-    menu: Res<ProcessMenu>,
-    mut file_line_query: Query<(Entity, &mut Node), With<FileLineBar>>,
-) -> Result {
-    for (idx, entity) in data.entities.iter().enumerate() {
-        let Some(entity) = entity else {
-            continue; // skip if entity is None
-        };
-        if menu.hide_done && matches!(data.state.status[idx], TaskStatus::Done) {
-            // despawn entity if it is done and hide_done is true
-            let Ok((_, mut node)) = file_line_query.get_mut(*entity) else {
-                continue;
-            };
-            node.display = Display::None;
-        } else {
-            // spawn entity if it is not done or hide_done is false
-            let Ok((_, mut node)) = file_line_query.get_mut(*entity) else {
-                continue;
-            };
-            node.display = Display::Flex;
-        }
-    }
-    Ok(())
-}
-
 // show  import type
 pub fn show_import_type(
     //process_menu: Res<ProcessMenu>,
@@ -40,336 +16,650 @@ pub fn show_import_type(
     mut text_query: Query<&mut Text>,
 ) -> Result {
     for (menu, child) in import_query.iter() {
-        let Some(button_type) = menu
+        let button_type = menu
             .button_type // button_type is a Box::new()
             .as_ref() // so,first we need to get a reference,it's Important.
-            .as_any() // then we can downcast it to MenuImportButton
-            .downcast_ref::<MenuImportButton>()
-        else {
-            continue;
-        };
+            .as_any(); // then we can downcast it to MenuImportButton
         // println!("MenuButton: {:?}", menu.button_type);
         // println!("ProcessMenu: {:?}", process_menu.import_type);
 
+        // only the buttons whose text follows their state
+        let label = if let Some(bt) = button_type.downcast_ref::<MenuImportButton>() {
+            bt.to_string()
+        } else if let Some(bt) = button_type.downcast_ref::<MenuPauseButton>() {
+            bt.to_string()
+        } else if let Some(bt) = button_type.downcast_ref::<MenuParallelButton>() {
+            bt.to_string()
+        } else if let Some(bt) = button_type.downcast_ref::<MenuPresetButton>() {
+            bt.to_string()
+        } else if let Some(bt) = button_type.downcast_ref::<MenuFilterButton>() {
+            bt.to_string()
+        } else if let Some(bt) = button_type.downcast_ref::<MenuSortButton>() {
+            bt.to_string()
+        } else if let Some(bt) = button_type.downcast_ref::<MenuOrderButton>() {
+            bt.to_string()
+        } else {
+            continue;
+        };
+
         let Ok(mut text) = text_query.get_mut(child[0]) else {
             continue;
         };
-        text.0 = button_type.to_string();
+        text.0 = label;
     }
     Ok(())
 }
-// refresh lines when import files changed
+// the height of a row and the gap to the next one
+const ROW_HEIGHT: f32 = 30.0;
+const ROW_STRIDE: f32 = ROW_HEIGHT + 5.0;
+// rows spawned above and below the viewport
+const OVERSCAN: usize = 5;
+
+// only the rows in the scroll viewport are spawned, at their position in the list
+// the rows are rebuilt when the lines change and updated in place when a status changes
+// the filter bar decides which lines are in the list and their order
 pub fn refresh_lines(
     mut commands: Commands,
-    container_query: Single<Entity, With<LinesContainer>>,
+    container_query: Single<(&ScrollPosition, &ComputedNode), With<LinesContainer>>,
+    content_query: Single<(Entity, &mut Node), (With<LinesContent>, Without<FileLineBar>)>,
+    mut row_query: Query<&mut Node, With<FileLineBar>>,
     mut data: ResMut<PathDatas>,
+    filter: Res<ListFilter>,
     font: Res<FontHandle>,
+    ffmpeg_args: Res<FfmpegArg>,
+    config: Res<AppConfig>,
+    mut shown: Local<Vec<usize>>,
+    mut filtered: Local<FilterInputs>,
+    mut visible: Local<Vec<Option<usize>>>,
 ) -> Result {
-    // no changes, just return
-    if !data.changed {
-        return Ok(());
+    let (scroll, computed) = *container_query;
+    // the lines that pass the filter bar, in display order
+    // the progress of the running tasks changes the data every frame, that alone keeps the list
+    if data.is_changed() || filter.is_changed() {
+        let inputs = filter.inputs(&data.state);
+        if filter.is_changed() || data.changed || *filtered != inputs {
+            *shown = filter.apply(&data.state);
+            *filtered = inputs;
+        }
     }
+    let (content_entity, mut content_node) = content_query.into_inner();
+    // the entities are bookkeeping, the lines did not change
+    let data = data.bypass_change_detection();
 
-    // clear container children
-    let container_entity = *container_query;
-    for entity in data.entities.iter() {
-        if let Some(e) = entity {
-            commands.entity(*e).try_despawn();
+    if data.changed {
+        for entity in data.entities.drain(..).flatten() {
+            commands.entity(entity).try_despawn();
         }
+        data.changed = false;
     }
-    data.entities.clear();
+    data.entities.resize(data.state.lines.len(), None);
 
-    let mut entities = vec![];
-    for (index, path) in data.state.lines.iter().enumerate() {
-        let total_secs = data.state.progress.get(&index).map_or(0, |s| s.total);
-        let progress = data
-            .state
-            .progress
-            .get(&index)
-            .and_then(|s| Some(s.percent))
-            .unwrap_or(0.0);
-        let n = 80;
-        let short_path = if path.chars().count() > n {
-            let start = path
-                .char_indices()
-                .rev()
-                .nth(n - 1)
-                .map(|(i, _)| i)
-                .unwrap();
-            &format!("... {}", &path[start..])
-        } else {
-            path
-        };
+    let height = Val::Px(shown.len() as f32 * ROW_STRIDE);
+    if content_node.height != height {
+        content_node.height = height;
+    }
 
-        // create row
-        let id = commands
-            .spawn((
-                FileLineBar,
-                Node {
-                    flex_direction: FlexDirection::Row,
-                    width: Val::Percent(100.0),
-                    height: Val::Px(30.0),
-                    column_gap: Val::Px(5.0),
-                    //align_self: AlignSelf::Stretch,
-                    ..default()
-                },
+    let viewport = computed.size().y * computed.inverse_scale_factor();
+    let first = ((scroll.offset_y / ROW_STRIDE).floor().max(0.0) as usize).saturating_sub(OVERSCAN);
+    let last = (((scroll.offset_y + viewport) / ROW_STRIDE).ceil().max(0.0) as usize + OVERSCAN)
+        .min(shown.len());
+    let first = first.min(last);
+    // the position of each line in the list, the buffer is kept between frames
+    visible.clear();
+    visible.resize(data.state.lines.len(), None);
+    for (position, index) in shown[first..last].iter().enumerate() {
+        visible[*index] = Some(first + position);
+    }
+
+    let preset_names = ffmpeg_args.preset_names();
+    for (index, position) in visible.iter().copied().enumerate() {
+        let top = position.map(|position| Val::Px(position as f32 * ROW_STRIDE));
+        match (data.entities[index], top) {
+            // scrolled out or hidden
+            (Some(entity), None) => {
+                commands.entity(entity).try_despawn();
+                data.entities[index] = None;
+            }
+            // the filter or the sort order changed
+            (Some(entity), Some(top)) => {
+                if let Ok(mut node) = row_query.get_mut(entity)
+                    && node.top != top
+                {
+                    node.top = top;
+                }
+            }
+            (None, Some(top)) => {
+                let id = spawn_row(
+                    &mut commands,
+                    &data.state,
+                    index,
+                    top,
+                    &preset_names,
+                    &font.0,
+                    &config.quality,
+                );
+                commands.entity(id).insert(ChildOf(content_entity));
+                data.entities[index] = Some(id);
+            }
+            (None, None) => {}
+        }
+    }
+    Ok(())
+}
+
+// the row of a line, with absolute position in the list content
+fn spawn_row(
+    commands: &mut Commands,
+    state: &FilesState,
+    index: usize,
+    top: Val,
+    preset_names: &[String],
+    font: &Handle<Font>,
+    quality_config: &QualityConfig,
+) -> Entity {
+    let path = &state.lines[index];
+    let total_secs = state.progress.get(&index).map_or(0, |s| s.total);
+    let progress = state.progress.get(&index).map_or(0.0, |s| s.percent);
+    let bar = state
+        .progress
+        .get(&index)
+        .and_then(|s| bar_color(state.status.get(index), s))
+        .unwrap_or(Color::srgb_u8(0, 250, 0));
+    let error = match state.errors.get(&index) {
+        Some(e) if matches!(state.status.get(index), Some(TaskStatus::Failed)) => {
+            format!("  {}", e)
+        }
+        _ => String::new(),
+    };
+    let media = media_label(state, index);
+    let (quality, quality_color) = quality_text(state, index, quality_config);
+    let n = 80;
+    let short_path = if path.chars().count() > n {
+        let start = path
+            .char_indices()
+            .rev()
+            .nth(n - 1)
+            .map(|(i, _)| i)
+            .unwrap();
+        &format!("... {}", &path[start..])
+    } else {
+        path
+    };
+
+    // create row
+    commands
+        .spawn((
+            FileLineBar,
+            Node {
+                flex_direction: FlexDirection::Row,
+                position_type: PositionType::Absolute,
+                top,
+                width: Val::Percent(100.0),
+                height: Val::Px(ROW_HEIGHT),
+                column_gap: Val::Px(5.0),
+                //align_self: AlignSelf::Stretch,
+                ..default()
+            },
+            AccessibilityNode(Accessible::new(Role::ListItem)),
+            Pickable {
+                should_block_lower: false,
+                ..default()
+            },
+            children![
                 AccessibilityNode(Accessible::new(Role::ListItem)),
                 Pickable {
                     should_block_lower: false,
                     ..default()
                 },
-                children![
+                // index number, click to select the line
+                (
                     AccessibilityNode(Accessible::new(Role::ListItem)),
                     Pickable {
                         should_block_lower: false,
                         ..default()
                     },
-                    // index number
-                    (
+                    Button,
+                    SelectButton,
+                    IndexOfline(index),
+                    Node {
+                        width: Val::Px(55.),
+                        height: Val::Px(30.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        //padding: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(5.0)),
+                    //BorderColor(Color::WHITE.with_alpha(0.2)),
+                    BackgroundColor(Color::WHITE.with_alpha(0.02)),
+                    children![(
                         AccessibilityNode(Accessible::new(Role::ListItem)),
                         Pickable {
                             should_block_lower: false,
                             ..default()
                         },
-                        Node {
-                            width: Val::Px(55.),
-                            height: Val::Px(30.0),
-                            border: UiRect::all(Val::Px(1.0)),
-                            // horizontally center child text
-                            justify_content: JustifyContent::Center,
-                            // vertically center child text
-                            align_items: AlignItems::Center,
-                            //padding: UiRect::all(Val::Px(5.0)),
+                        Text::new(format!("{:0>3}", index + 1)),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 12.0,
                             ..default()
                         },
-                        BorderRadius::all(Val::Px(5.0)),
-                        //BorderColor(Color::WHITE.with_alpha(0.2)),
-                        BackgroundColor(Color::WHITE.with_alpha(0.02)),
-                        children![(
-                            AccessibilityNode(Accessible::new(Role::ListItem)),
-                            Pickable {
-                                should_block_lower: false,
-                                ..default()
-                            },
-                            Text::new(format!("{:0>3}", index + 1)),
-                            TextFont {
-                                font: font.0.clone(),
-                                font_size: 12.0,
-                                ..default()
-                            },
-                            TextColor(Color::srgb(0.9, 0.9, 0.9)),
-                            //TextShadow::default(),
-                        )],
-                    ),
-                    // total seconds
-                    (
+                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                        //TextShadow::default(),
+                    )],
+                ),
+                // total seconds
+                (
+                    AccessibilityNode(Accessible::new(Role::ListItem)),
+                    Pickable {
+                        should_block_lower: false,
+                        ..default()
+                    },
+                    Node {
+                        width: Val::Px(85.),
+                        height: Val::Px(30.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        // horizontally center child text
+                        justify_content: JustifyContent::Center,
+                        // vertically center child text
+                        align_items: AlignItems::Center,
+                        //padding: UiRect::all(Val::Px(5.0)),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(5.0)),
+                    //BorderColor(Color::WHITE.with_alpha(0.2)),
+                    BackgroundColor(Color::WHITE.with_alpha(0.1)),
+                    children![(
                         AccessibilityNode(Accessible::new(Role::ListItem)),
                         Pickable {
                             should_block_lower: false,
                             ..default()
                         },
-                        Node {
-                            width: Val::Px(85.),
-                            height: Val::Px(30.0),
-                            border: UiRect::all(Val::Px(1.0)),
-                            // horizontally center child text
-                            justify_content: JustifyContent::Center,
-                            // vertically center child text
-                            align_items: AlignItems::Center,
-                            //padding: UiRect::all(Val::Px(5.0)),
+                        Text::new(format!("{:>}", total_secs)),
+                        IndexOfline(index),
+                        TextFont {
+                            font: font.clone(),
+                            font_size: 12.0,
                             ..default()
                         },
-                        BorderRadius::all(Val::Px(5.0)),
-                        //BorderColor(Color::WHITE.with_alpha(0.2)),
-                        BackgroundColor(Color::WHITE.with_alpha(0.1)),
-                        children![(
+                        TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                        //TextShadow::default(),
+                    )],
+                ),
+                // task button
+                // one task button for each preset
+                ui_task_buttons(index, preset_names.to_vec(), font.clone()),
+                // replace button
+                ui_replace_button(index, font.clone()),
+                // undo replace button
+                ui_undo_button(index, font.clone()),
+                // snapshot button A
+                ui_snap_button(index, font.clone(), true),
+                // snapshot button B
+                ui_snap_button(index, font.clone(), false),
+                // open button
+                ui_open_button(index, font.clone()),
+                // reveal output button
+                ui_reveal_button(index, font.clone()),
+                // copy path button
+                ui_copy_button(index, font.clone()),
+                // compare button
+                ui_compare_button(index, font.clone()),
+                // edit button
+                ui_edit_button(index, font.clone()),
+                // log button
+                ui_log_button(index, font.clone()),
+                // details button
+                ui_details_button(index, font.clone()),
+                // info layout (right)
+                (
+                    AccessibilityNode(Accessible::new(Role::ListItem)),
+                    Pickable {
+                        should_block_lower: false,
+                        ..default()
+                    },
+                    Node {
+                        width: Val::Percent(100.),
+                        height: Val::Px(30.0),
+                        border: UiRect::all(Val::Px(1.0)),
+                        flex_direction: FlexDirection::Column,
+                        justify_content: JustifyContent::Start,
+                        align_items: AlignItems::Start,
+                        row_gap: Val::Px(2.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(5.0)),
+                    BorderColor(Color::WHITE.with_alpha(0.2)),
+                    children![
+                        // path text
+                        (
                             AccessibilityNode(Accessible::new(Role::ListItem)),
                             Pickable {
                                 should_block_lower: false,
                                 ..default()
                             },
-                            Text::new(format!("{:>}", total_secs)),
-                            IndexOfline(index),
-                            TextFont {
-                                font: font.0.clone(),
-                                font_size: 12.0,
+                            Node {
+                                width: Val::Percent(100.),
+                                height: Val::Px(25.0),
+                                //border: UiRect::all(Val::Px(1.0)),
+                                // horizontally center child text
+                                justify_content: JustifyContent::Start,
+                                // vertically center child text
+                                align_items: AlignItems::Center,
+                                padding: UiRect::all(Val::Px(5.0)),
                                 ..default()
                             },
-                            TextColor(Color::srgb(0.9, 0.9, 0.9)),
-                            //TextShadow::default(),
-                        )],
-                    ),
-                    // task button
-                    ui_task_button(index, font.0.clone()),
-                    // libx265
-                    ui_task_ex_button(index, font.0.clone()),
-                    // replace button
-                    ui_replace_button(index, font.0.clone()),
-                    // snapshot button A
-                    ui_snap_button(index, font.0.clone(), true),
-                    // snapshot button B
-                    ui_snap_button(index, font.0.clone(), false),
-                    // open button
-                    ui_open_button(index, font.0.clone()),
-                    // info layout (right)
-                    (
-                        AccessibilityNode(Accessible::new(Role::ListItem)),
-                        Pickable {
-                            should_block_lower: false,
-                            ..default()
-                        },
-                        Node {
-                            width: Val::Percent(100.),
-                            height: Val::Px(30.0),
-                            border: UiRect::all(Val::Px(1.0)),
-                            flex_direction: FlexDirection::Column,
-                            justify_content: JustifyContent::Start,
-                            align_items: AlignItems::Start,
-                            row_gap: Val::Px(2.0),
-                            ..default()
-                        },
-                        BorderRadius::all(Val::Px(5.0)),
-                        BorderColor(Color::WHITE.with_alpha(0.2)),
-                        children![
-                            // path text
-                            (
+                            children![(
                                 AccessibilityNode(Accessible::new(Role::ListItem)),
                                 Pickable {
                                     should_block_lower: false,
                                     ..default()
                                 },
-                                Node {
-                                    width: Val::Percent(100.),
-                                    height: Val::Px(25.0),
-                                    //border: UiRect::all(Val::Px(1.0)),
-                                    // horizontally center child text
-                                    justify_content: JustifyContent::Start,
-                                    // vertically center child text
-                                    align_items: AlignItems::Center,
-                                    padding: UiRect::all(Val::Px(5.0)),
+                                Text::new(short_path),
+                                TextFont {
+                                    font: font.clone(),
+                                    font_size: 12.0,
                                     ..default()
                                 },
-                                children![(
-                                    AccessibilityNode(Accessible::new(Role::ListItem)),
-                                    Pickable {
-                                        should_block_lower: false,
-                                        ..default()
-                                    },
-                                    Text::new(short_path),
-                                    TextFont {
-                                        font: font.0.clone(),
-                                        font_size: 12.0,
-                                        ..default()
-                                    },
-                                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
-                                    //TextShadow::default(),
-                                )],
-                            ),
-                            // bar
-                            (
-                                AccessibilityNode(Accessible::new(Role::ListItem)),
-                                Pickable {
-                                    should_block_lower: false,
-                                    ..default()
-                                },
-                                ProgressBar,
-                                IndexOfline(index),
-                                Node {
-                                    width: Val::Percent(progress as f32), // initially 0%
-                                    height: Val::Px(3.0),
-                                    ..default()
-                                },
-                                BackgroundColor(Color::srgb_u8(0, 250, 0)),
-                            )
-                        ]
-                    )
-                ],
-            ))
-            .insert(ChildOf(container_entity))
-            .id();
-        entities.push(Some(id));
-    }
-    data.entities = entities;
-    data.changed = false;
-
-    return Ok(());
+                                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                                //TextShadow::default(),
+                                children![
+                                    // codec and resolution of the source
+                                    (
+                                        TextSpan::new(media),
+                                        MediaText,
+                                        IndexOfline(index),
+                                        TextFont {
+                                            font: font.clone(),
+                                            font_size: 12.0,
+                                            ..default()
+                                        },
+                                        TextColor(Color::srgb_u8(150, 150, 220)),
+                                    ),
+                                    // the quality score of the output
+                                    (
+                                        TextSpan::new(quality),
+                                        QualityText,
+                                        IndexOfline(index),
+                                        TextFont {
+                                            font: font.clone(),
+                                            font_size: 12.0,
+                                            ..default()
+                                        },
+                                        TextColor(quality_color),
+                                    ),
+                                    // live statistics of a running task
+                                    (
+                                        TextSpan::new(""),
+                                        StatsText,
+                                        IndexOfline(index),
+                                        TextFont {
+                                            font: font.clone(),
+                                            font_size: 12.0,
+                                            ..default()
+                                        },
+                                        TextColor(Color::srgb_u8(120, 200, 120)),
+                                    ),
+                                    // the last error line of a failed task
+                                    (
+                                        TextSpan::new(error),
+                                        ErrorText,
+                                        IndexOfline(index),
+                                        TextFont {
+                                            font: font.clone(),
+                                            font_size: 12.0,
+                                            ..default()
+                                        },
+                                        TextColor(Color::srgb_u8(250, 80, 80)),
+                                    )
+                                ],
+                            )],
+                        ),
+                        // bar
+                        (
+                            AccessibilityNode(Accessible::new(Role::ListItem)),
+                            Pickable {
+                                should_block_lower: false,
+                                ..default()
+                            },
+                            ProgressBar,
+                            IndexOfline(index),
+                            Node {
+                                width: Val::Percent(progress as f32), // initially 0%
+                                height: Val::Px(3.0),
+                                ..default()
+                            },
+                            BackgroundColor(bar),
+                        )
+                    ]
+                )
+            ],
+        ))
+        .id()
 }
 
 pub fn progress_bar_update(
-    mut process_state: ResMut<ProcessState>,
-    mut paths_data: ResMut<PathDatas>,
+    paths_data: Res<PathDatas>,
+    mut changed: EventReader<ProgressChanged>,
     mut bar_query: Query<(&mut Node, &IndexOfline, &mut BackgroundColor), With<ProgressBar>>,
     mut text_query: Query<
         (&mut Text, &IndexOfline),
         (With<IndexOfline>, With<Text>, Without<ProgressBar>),
     >,
+    mut stats_query: Query<(&mut TextSpan, &IndexOfline), With<StatsText>>,
 ) {
-    //let mut rx = process_state.progress_tx.subscribe();
-    //paths_data.changed = true; // mark data as changed
+    for ProgressChanged(idx) in changed.read() {
+        let idx = *idx;
+        let Some(statistics) = paths_data.state.progress.get(&idx) else {
+            continue;
+        };
+        let status = paths_data.state.status.get(idx);
+        let running = matches!(status, Some(TaskStatus::Running));
 
-    // 处理接收的消息
-    let Ok(message) = process_state.progress_rx.try_recv() else {
-        return;
-    };
+        let bar_color = bar_color(status, statistics);
 
-    let progress = &mut paths_data.state.progress;
+        for (mut node, bar_idx, mut bgcolor) in bar_query.iter_mut() {
+            if bar_idx.0 == idx {
+                // update bar width
+                node.width = Val::Percent(statistics.percent as f32);
+                if let Some(color) = bar_color {
+                    bgcolor.0 = color;
+                }
+                break;
+            }
+        }
 
-    let Some(idx) = message.progress_index else {
-        //info!("Received none (index): {:?}", message);
-        return;
-    };
+        // update total seconds text
+        for (mut text, text_idx) in text_query.iter_mut() {
+            if text_idx.0 == idx {
+                // update text with total seconds
+                text.0 = format!("{:>6}", statistics.total);
+                break;
+            }
+        }
 
-    // target progress statistics
-    // Use entry API to avoid temporary value issues
-    let statistics = progress.entry(idx).or_insert(ProgressStatistics {
-        total: 0,
-        current: 0,
-        percent: 0.0,
-    });
+        // update fps, speed, size and eta
+        for (mut span, span_idx) in stats_query.iter_mut() {
+            if span_idx.0 == idx {
+                span.0 = if running {
+                    stats_label(statistics)
+                } else {
+                    String::new()
+                };
+                break;
+            }
+        }
+    }
+}
 
-    match message.progress_type {
-        ProgressType::Total => {
-            statistics.total = message.progress_value;
-            statistics.current = 0; // reset current when total is set
+// green while running, blue when done, red when failed, orange when interrupted
+fn bar_color(status: Option<&TaskStatus>, statistics: &ProgressStatistics) -> Option<Color> {
+    match status {
+        // the analysis pass of a two-pass encode
+        Some(TaskStatus::Running) if matches!(statistics.pass, Some((1, passes)) if passes > 1) => {
+            Some(Color::srgb_u8(0, 180, 250))
         }
-        ProgressType::Current => {
-            statistics.current = message.progress_value;
+        Some(TaskStatus::Running) if statistics.current > 0 => Some(Color::srgb_u8(0, 250, 0)),
+        Some(TaskStatus::Done | TaskStatus::Replaced) => Some(Color::srgb_u8(0, 0, 250)),
+        Some(TaskStatus::Failed) => Some(Color::srgb_u8(250, 0, 0)),
+        Some(TaskStatus::Interrupted) => Some(Color::srgb_u8(250, 160, 0)),
+        _ => None,
+    }
+}
+
+// the live statistics shown after the path of a running task
+fn stats_label(statistics: &ProgressStatistics) -> String {
+    let stats = &statistics.stats;
+    let eta = statistics
+        .eta
+        .map(format_duration)
+        .unwrap_or_else(|| "--:--:--".to_string());
+    let pass = statistics
+        .pass
+        .map(|(pass, passes)| format!(" pass {}/{}", pass, passes))
+        .unwrap_or_default();
+    format!(
+        "  |{} {:.0}fps {:.2}x {:.0}kbits/s {} eta {}",
+        pass,
+        stats.fps,
+        stats.speed,
+        stats.bitrate,
+        format_size(stats.total_size),
+        eta
+    )
+}
+
+// show the last error line of failed tasks
+pub fn update_error_text(
+    data: Res<PathDatas>,
+    mut span_query: Query<(&mut TextSpan, &IndexOfline), With<ErrorText>>,
+) {
+    if !data.is_changed() {
+        return;
+    }
+    for (mut span, idx) in span_query.iter_mut() {
+        let error = match data.state.errors.get(&idx.0) {
+            Some(e) if matches!(data.state.status.get(idx.0), Some(TaskStatus::Failed)) => {
+                format!("  {}", e)
+            }
+            _ => String::new(),
+        };
+        if span.0 != error {
+            span.0 = error;
         }
     }
+}
 
-    // update percent
-    statistics.percent = if statistics.total > 0 {
-        (statistics.current as f64 / statistics.total as f64) * 100.0
-    } else {
-        0.0
+// show the ffmpeg output of the selected line
+pub fn log_window_update(
+    menu: Res<ProcessMenu>,
+    logs: Res<TaskLogs>,
+    window: Single<(&mut Visibility, &mut ScrollPosition), With<LogWindow>>,
+    mut text: Single<&mut Text, With<LogText>>,
+) {
+    if !menu.is_changed() && !logs.is_changed() {
+        return;
+    }
+    let (mut visibility, mut scroll) = window.into_inner();
+    let Some(index) = menu.log_index else {
+        *visibility = Visibility::Hidden;
+        return;
+    };
+    *visibility = Visibility::Visible;
+    text.0 = match logs.0.get(&index) {
+        Some(lines) => lines.iter().cloned().collect::<Vec<_>>().join("\n"),
+        None => format!("no output for line {}", index + 1),
     };
+    // follow the tail of the output
+    scroll.offset_y = f32::MAX;
+}
 
-    for (mut node, idx, _) in bar_query.iter_mut() {
-        if idx.0 == message.progress_index.unwrap() {
-            // update bar width
-            node.width = Val::Percent(statistics.percent as f32);
-            break;
-        }
+// the codec and resolution of the source and the cuts of the edit panel
+fn media_label(state: &FilesState, index: usize) -> String {
+    let mut label = state
+        .media
+        .get(&index)
+        .map(|m| format!("  [{}]", m.summary()))
+        .unwrap_or_default();
+    if let Some(edit) = state.edits.get(&index) {
+        label.push_str(&format!("  <{}>", edit.summary()));
     }
+    label
+}
 
-    // update total seconds text
-    for (mut text, idx) in text_query.iter_mut() {
-        if idx.0 == message.progress_index.unwrap() {
-            // update text with total seconds
-            text.0 = format!("{:>6}", statistics.total);
-            break;
+// show the codec and resolution of analyzed files
+pub fn update_media_text(
+    data: Res<PathDatas>,
+    mut span_query: Query<(&mut TextSpan, &IndexOfline), With<MediaText>>,
+) {
+    if !data.is_changed() {
+        return;
+    }
+    for (mut span, idx) in span_query.iter_mut() {
+        let media = media_label(&data.state, idx.0);
+        if span.0 != media {
+            span.0 = media;
         }
     }
+}
 
-    // done
-    if statistics.percent >= 99. {
-        // change bar color to blue
-        for (_, idx, mut bgcolor) in bar_query.iter_mut() {
-            if idx.0 == message.progress_index.unwrap() {
-                bgcolor.0 = Color::srgb_u8(0, 0, 250);
-                break;
-            }
+// the score of a finished line, red when it blocks the replace
+fn quality_text(state: &FilesState, index: usize, config: &QualityConfig) -> (String, Color) {
+    let Some(score) = state.quality.get(&index) else {
+        return (String::new(), Color::WHITE);
+    };
+    let color = match state.quality_check(index, config) {
+        Ok(()) if matches!(score, QualityScore::Score(..)) => Color::srgb_u8(120, 220, 120),
+        Ok(()) => Color::srgb_u8(160, 160, 160),
+        Err(_) => Color::srgb_u8(250, 80, 80),
+    };
+    (format!("  {}", score.label()), color)
+}
+
+pub fn update_quality_text(
+    data: Res<PathDatas>,
+    config: Res<AppConfig>,
+    mut span_query: Query<(&mut TextSpan, &mut TextColor, &IndexOfline), With<QualityText>>,
+) {
+    if !data.is_changed() && !config.is_changed() {
+        return;
+    }
+    for (mut span, mut color, idx) in span_query.iter_mut() {
+        let (text, text_color) = quality_text(&data.state, idx.0, &config.quality);
+        if span.0 != text {
+            span.0 = text;
         }
-        paths_data.state.status[idx] = TaskStatus::Done;
+        if color.0 != text_color {
+            color.0 = text_color;
+        }
+    }
+}
+
+// show the media information of the selected line
+pub fn details_window_update(
+    menu: Res<ProcessMenu>,
+    data: Res<PathDatas>,
+    mut window: Single<&mut Visibility, With<DetailsWindow>>,
+    mut text: Single<&mut Text, With<DetailsText>>,
+) {
+    if !menu.is_changed() && !data.is_changed() {
+        return;
+    }
+    let Some(index) = menu.details_index else {
+        **window = Visibility::Hidden;
+        return;
+    };
+    **window = Visibility::Visible;
+    let path = data.state.lines.get(index).cloned().unwrap_or_default();
+    let details = match data.state.media.get(&index) {
+        Some(info) => info.details(),
+        None => "not analyzed yet".to_string(),
+    };
+    let content = format!("{}\n{}", path, details);
+    if text.0 != content {
+        text.0 = content;
     }
 }
@@ -5,9 +5,15 @@ use bevy::{
     input::mouse::{MouseScrollUnit, MouseWheel},
     picking::hover::HoverMap,
 };
-use tokio::sync::{broadcast, mpsc};
 
-use crate::{FONT_BYTES, ui::ui_menu_button};
+use crate::utility::probe_hardware;
+use crate::utility::session::session_files;
+use crate::utility::watch::start_watch;
+use crate::{
+    FONT_BYTES,
+    ui::{ui_compare_control, ui_edit_control, ui_menu_button},
+};
+use bevy::ui::RelativeCursorPosition;
 const LINE_HEIGHT: f32 = 30.0;
 
 // initialize
@@ -22,6 +28,16 @@ pub fn setup(
     let font_handle = fonts.add(font);
     commands.insert_resource(FontHandle(font_handle.clone()));
 
+    let mut ffmpeg_arg = FfmpegArg::load();
+    // check the hardware presets against the local ffmpeg build
+    let hardware_message = match probe_hardware() {
+        Ok((backend, encoders)) => ffmpeg_arg.apply_hardware(backend, &encoders),
+        Err(e) => Some(e),
+    };
+    let preset_button = MenuPresetButton::new(ffmpeg_arg.preset_names());
+    let preset = preset_button.current().to_string();
+    let config = AppConfig::load();
+
     // layout
     let layout_id = commands
         .spawn((
@@ -43,10 +59,14 @@ pub fn setup(
         .spawn((
             Node {
                 width: Val::Percent(100.0),
-                height: Val::Px(36.0),
+                // the buttons wrap into more rows on a narrow window
+                min_height: Val::Px(36.0),
+                flex_shrink: 0.0,
                 position_type: PositionType::Relative,
                 flex_direction: FlexDirection::Row,
+                flex_wrap: FlexWrap::Wrap,
                 column_gap: Val::Px(5.0),
+                row_gap: Val::Px(5.0),
                 justify_content: JustifyContent::FlexStart,
                 align_items: AlignItems::Start,
                 ..default()
@@ -54,10 +74,25 @@ pub fn setup(
             BackgroundColor(Color::srgb_u8(50, 50, 50)),
             children![
                 ui_menu_button(MenuImportButton::default(), font_handle.clone()),
+                ui_menu_button(MenuImportFilesButton, font_handle.clone()),
                 ui_menu_button(MenuSaveButton::default(), font_handle.clone()),
                 ui_menu_button(MenuLoadButton::default(), font_handle.clone()),
                 ui_menu_button(MenuClearButton::default(), font_handle.clone()),
-                ui_menu_button(MenuHideButton::default(), font_handle.clone()),
+                ui_menu_button(MenuSheetButton::default(), font_handle.clone()),
+                ui_menu_button(
+                    MenuQualityButton {
+                        checked: config.quality.enabled,
+                    },
+                    font_handle.clone(),
+                ),
+                ui_menu_button(
+                    MenuThresholdButton::new(&config.quality),
+                    font_handle.clone()
+                ),
+                ui_menu_button(preset_button, font_handle.clone()),
+                ui_menu_button(MenuStartAllButton::default(), font_handle.clone()),
+                ui_menu_button(MenuPauseButton::default(), font_handle.clone()),
+                ui_menu_button(MenuParallelButton::default(), font_handle.clone()),
                 ui_menu_button(MenuToggleSetting::default(), font_handle.clone()),
                 ui_menu_button(MenuExitButton::default(), font_handle.clone()),
             ],
@@ -69,6 +104,7 @@ pub fn setup(
     let preview_id = commands
         .spawn((
             PreviewWindow,
+            RelativeCursorPosition::default(),
             Node {
                 width: Val::Percent(25.0),
                 height: Val::Percent(25.0),
@@ -86,28 +122,293 @@ pub fn setup(
         .id();
     commands.entity(layout_id).add_child(preview_id);
 
-    let (progress_tx, progress_rx) = mpsc::channel::<ProgressInfo>(100);
-    let (toast_tx, toast_rx) = mpsc::channel::<String>(100);
-    let (main_tx, _) = broadcast::channel::<ProcessSignal>(100);
-    //let progress = HashMap::<usize, ProgressStatistics>::new();
-
-    commands.insert_resource(ProcessState {
-        progress_tx,
-        progress_rx,
-        main_tx,
-        layout: Some(layout_id),
-        toast_message: Vec::new(),
-        toast_rx,
-        toast_tx,
-    });
+    // log window (ffmpeg output of a task)
+    let log_id = commands
+        .spawn((
+            LogWindow,
+            Node {
+                width: Val::Percent(60.0),
+                height: Val::Percent(40.0),
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(5.0)),
+                flex_direction: FlexDirection::Column,
+                overflow: Overflow::scroll_y(),
+                ..default()
+            },
+            ZIndex(98),
+            Visibility::Hidden,
+            BackgroundColor(Color::srgb_u8(20, 20, 20).with_alpha(0.95)),
+            children![(
+                LogText,
+                Text::new(""),
+                TextFont {
+                    font: font_handle.clone(),
+                    font_size: 11.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.8, 0.8, 0.8)),
+            )],
+        ))
+        .id();
+    commands.entity(layout_id).add_child(log_id);
+
+    // details window (media information of a file)
+    let details_id = commands
+        .spawn((
+            DetailsWindow,
+            Node {
+                width: Val::Px(360.0),
+                position_type: PositionType::Absolute,
+                top: Val::Px(50.0),
+                right: Val::Px(10.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ZIndex(98),
+            Visibility::Hidden,
+            BorderRadius::all(Val::Px(5.0)),
+            BackgroundColor(Color::srgb_u8(20, 20, 20).with_alpha(0.95)),
+            children![(
+                DetailsText,
+                Text::new(""),
+                TextFont {
+                    font: font_handle.clone(),
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            )],
+        ))
+        .id();
+    commands.entity(layout_id).add_child(details_id);
+
+    // comparison window (the same frame of the source and the output)
+    let compare_id = commands
+        .spawn((
+            CompareWindow,
+            Node {
+                width: Val::Percent(80.0),
+                height: Val::Percent(80.0),
+                position_type: PositionType::Absolute,
+                top: Val::Percent(10.0),
+                left: Val::Percent(10.0),
+                padding: UiRect::all(Val::Px(5.0)),
+                row_gap: Val::Px(5.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ZIndex(100),
+            Visibility::Hidden,
+            BorderRadius::all(Val::Px(5.0)),
+            BackgroundColor(Color::srgb_u8(20, 20, 20).with_alpha(0.98)),
+            children![
+                (
+                    Node {
+                        width: Val::Percent(100.0),
+                        column_gap: Val::Px(5.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    children![
+                        ui_compare_control(CompareAction::Back, "-10s", font_handle.clone()),
+                        ui_compare_control(
+                            CompareAction::PrevFrame,
+                            "< frame",
+                            font_handle.clone()
+                        ),
+                        ui_compare_control(
+                            CompareAction::NextFrame,
+                            "frame >",
+                            font_handle.clone()
+                        ),
+                        ui_compare_control(CompareAction::Forward, "+10s", font_handle.clone()),
+                        ui_compare_control(
+                            CompareAction::Mode,
+                            "split / side",
+                            font_handle.clone()
+                        ),
+                        ui_compare_control(CompareAction::ZoomOut, "zoom -", font_handle.clone()),
+                        ui_compare_control(CompareAction::ZoomIn, "zoom +", font_handle.clone()),
+                        ui_compare_control(CompareAction::Close, "close", font_handle.clone()),
+                        (
+                            CompareText,
+                            Text::new(""),
+                            TextFont {
+                                font: font_handle.clone(),
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                        ),
+                    ],
+                ),
+                (
+                    Node {
+                        width: Val::Percent(100.0),
+                        flex_grow: 1.0,
+                        min_height: Val::Px(0.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    children![(
+                        CompareImage,
+                        ImageNode::default(),
+                        RelativeCursorPosition::default(),
+                        Node {
+                            max_width: Val::Percent(100.0),
+                            max_height: Val::Percent(100.0),
+                            ..default()
+                        },
+                    )],
+                ),
+            ],
+        ))
+        .id();
+    commands.entity(layout_id).add_child(compare_id);
+
+    // edit window (trim, crop and segments of a file)
+    let edit_id = commands
+        .spawn((
+            EditWindow,
+            Node {
+                width: Val::Percent(80.0),
+                height: Val::Percent(80.0),
+                position_type: PositionType::Absolute,
+                top: Val::Percent(10.0),
+                left: Val::Percent(10.0),
+                padding: UiRect::all(Val::Px(5.0)),
+                row_gap: Val::Px(5.0),
+                flex_direction: FlexDirection::Column,
+                ..default()
+            },
+            ZIndex(100),
+            Visibility::Hidden,
+            BorderRadius::all(Val::Px(5.0)),
+            BackgroundColor(Color::srgb_u8(20, 20, 20).with_alpha(0.98)),
+            children![
+                (
+                    Node {
+                        width: Val::Percent(100.0),
+                        column_gap: Val::Px(5.0),
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    children![
+                        ui_edit_control(EditAction::Back, "-1s", font_handle.clone()),
+                        ui_edit_control(EditAction::Forward, "+1s", font_handle.clone()),
+                        ui_edit_control(EditAction::SetIn, "set in", font_handle.clone()),
+                        ui_edit_control(EditAction::SetOut, "set out", font_handle.clone()),
+                        ui_edit_control(EditAction::ClearTrim, "clear trim", font_handle.clone()),
+                        ui_edit_control(EditAction::ClearCrop, "clear crop", font_handle.clone()),
+                        ui_edit_control(EditAction::Segment, "segments", font_handle.clone()),
+                        ui_edit_control(EditAction::Close, "close", font_handle.clone()),
+                        (
+                            EditText,
+                            Text::new(""),
+                            TextFont {
+                                font: font_handle.clone(),
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                        ),
+                    ],
+                ),
+                (
+                    Node {
+                        width: Val::Percent(100.0),
+                        flex_grow: 1.0,
+                        min_height: Val::Px(0.0),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        overflow: Overflow::clip(),
+                        ..default()
+                    },
+                    children![(
+                        EditImage,
+                        ImageNode::default(),
+                        RelativeCursorPosition::default(),
+                        Node {
+                            max_width: Val::Percent(100.0),
+                            max_height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        children![(
+                            EditOverlay::CropBox,
+                            Node {
+                                position_type: PositionType::Absolute,
+                                border: UiRect::all(Val::Px(2.0)),
+                                ..default()
+                            },
+                            BorderColor(Color::srgb_u8(250, 220, 0)),
+                            Visibility::Hidden,
+                            Pickable::IGNORE,
+                        )],
+                    )],
+                ),
+                (
+                    EditTimeline,
+                    RelativeCursorPosition::default(),
+                    Node {
+                        width: Val::Percent(100.0),
+                        height: Val::Px(24.0),
+                        ..default()
+                    },
+                    BorderRadius::all(Val::Px(3.0)),
+                    BackgroundColor(Color::srgb_u8(50, 50, 50)),
+                    children![
+                        (
+                            EditOverlay::Range,
+                            Node {
+                                position_type: PositionType::Absolute,
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgb_u8(0, 160, 0).with_alpha(0.6)),
+                            Pickable::IGNORE,
+                        ),
+                        (
+                            EditOverlay::Cursor,
+                            Node {
+                                position_type: PositionType::Absolute,
+                                width: Val::Px(2.0),
+                                height: Val::Percent(100.0),
+                                ..default()
+                            },
+                            BackgroundColor(Color::WHITE),
+                            Pickable::IGNORE,
+                        ),
+                    ],
+                ),
+            ],
+        ))
+        .id();
+    commands.entity(layout_id).add_child(edit_id);
+
+    let mut process_state = ProcessState::new(Some(layout_id));
+    process_state.toast_message.extend(hardware_message);
+    commands.insert_resource(process_state);
 
     commands.insert_resource(ProcessMenu {
         import_type: MenuImportButton::Sequence,
-        hide_done: false,
         toggle_setting: false,
+        log_index: None,
+        details_index: None,
+        preset,
+        contact_sheet: false,
     });
 
-    commands.init_resource::<FfmpegArg>();
+    commands.insert_resource(ffmpeg_arg);
+    commands.insert_resource(ProfileRules::load());
+    commands.insert_resource(start_watch(config.watch.clone(), session_files()));
+    commands.insert_resource(config);
+    commands.init_resource::<TaskQueue>();
+    commands.init_resource::<TaskLogs>();
 
     app_state.set(AppState::Monitor);
 }
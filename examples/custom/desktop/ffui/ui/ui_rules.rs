@@ -0,0 +1,87 @@
+use bevy::ecs::spawn::SpawnIter;
+use bevy::prelude::*;
+
+use crate::define::{ProfileRules, RuleToggleButton};
+
+pub fn rules_panel(rules: &ProfileRules, font: Handle<Font>) -> impl Bundle {
+    Children::spawn((
+        // title
+        Spawn((
+            Text::new("Rules (first match wins)"),
+            TextFont {
+                font: font.clone(),
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        )),
+        // one row for each rule
+        SpawnIter(
+            rules
+                .rules
+                .clone()
+                .into_iter()
+                .enumerate()
+                .map(move |(index, rule)| {
+                    (
+                        Node {
+                            width: Val::Percent(100.0),
+                            height: Val::Px(30.0),
+                            position_type: PositionType::Relative,
+                            padding: UiRect::all(Val::Px(5.0)),
+                            flex_direction: FlexDirection::Row,
+                            column_gap: Val::Px(5.0),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        children![
+                            // enable or disable the rule
+                            (
+                                Button,
+                                RuleToggleButton(index),
+                                Node {
+                                    width: Val::Px(40.0),
+                                    height: Val::Px(24.0),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BorderRadius::all(Val::Px(5.0)),
+                                BackgroundColor(Color::srgb_u8(16, 16, 16)),
+                                children![(
+                                    Text::new(if rule.enabled { "on" } else { "off" }),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 12.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                                )],
+                            ),
+                            // the text of rule
+                            (
+                                Node {
+                                    width: Val::Percent(100.0),
+                                    height: Val::Px(24.0),
+                                    padding: UiRect::all(Val::Px(5.0)),
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                BorderRadius::all(Val::Px(5.0)),
+                                BackgroundColor(Color::WHITE.with_alpha(0.1)),
+                                children![(
+                                    Text::new(rule.to_string()),
+                                    TextFont {
+                                        font: font.clone(),
+                                        font_size: 12.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::srgb(0.9, 0.9, 0.9)),
+                                )],
+                            ),
+                        ],
+                    )
+                }),
+        ),
+    ))
+}
@@ -1,3 +1,4 @@
+use bevy::ecs::spawn::{SpawnIter, SpawnWith};
 use bevy::{
     input_focus::{
         InputFocus,
@@ -5,13 +6,26 @@ use bevy::{
     },
     prelude::*,
 };
-use bevy_ecs::spawn::{SpawnIter, SpawnWith};
 
-use crate::define::ArgKeyValue;
+use crate::define::{
+    ArgField, ArgFieldKind, ArgGroup, ArgInput, ArgKeyValue, ArgRowAction, ArgRowButton,
+    ArgSubmitButton,
+};
+
+// focus the box when clicked, the keyboard input goes to the focused box
+pub fn focus_on_click(mut trigger: Trigger<Pointer<Click>>, mut focus: ResMut<InputFocus>) {
+    focus.0 = Some(trigger.target());
+    trigger.propagate(false);
+}
 
-pub fn text_input_panel(font: Handle<Font>) -> impl Bundle {
+pub fn text_input_panel(font: Handle<Font>, group: ArgGroup) -> impl Bundle {
     Children::spawn(SpawnWith(move |p: &mut ChildSpawner| {
+        // key
         p.spawn((
+            ArgInput {
+                group,
+                kind: ArgFieldKind::Key,
+            },
             Node {
                 width: Val::Percent(50.0),
                 height: Val::Percent(100.0),
@@ -25,15 +39,23 @@ pub fn text_input_panel(font: Handle<Font>) -> impl Bundle {
             BorderRadius::all(Val::Px(5.0)),
             BackgroundColor(Color::WHITE.with_alpha(0.1)),
             TabIndex(0),
+            children![(
+                Text::new(""),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            )],
         ))
-        .observe(
-            |mut trigger: Trigger<Pointer<Click>>, mut focus: ResMut<InputFocus>| {
-                focus.0 = Some(trigger.target());
-                trigger.propagate(false);
-            },
-        );
+        .observe(focus_on_click);
         // value
         p.spawn((
+            ArgInput {
+                group,
+                kind: ArgFieldKind::Value,
+            },
             Node {
                 width: Val::Percent(50.0),
                 height: Val::Percent(100.0),
@@ -47,16 +69,21 @@ pub fn text_input_panel(font: Handle<Font>) -> impl Bundle {
             BorderRadius::all(Val::Px(5.0)),
             BackgroundColor(Color::WHITE.with_alpha(0.1)),
             TabIndex(0),
+            children![(
+                Text::new(""),
+                TextFont {
+                    font: font.clone(),
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::srgb(0.9, 0.9, 0.9)),
+            )],
         ))
-        .observe(
-            |mut trigger: Trigger<Pointer<Click>>, mut focus: ResMut<InputFocus>| {
-                focus.0 = Some(trigger.target());
-                trigger.propagate(false);
-            },
-        );
+        .observe(focus_on_click);
         // submit
         p.spawn((
             Button,
+            ArgSubmitButton(group),
             Node {
                 width: Val::Px(50.0),
                 height: Val::Percent(100.0),
@@ -83,7 +110,47 @@ pub fn text_input_panel(font: Handle<Font>) -> impl Bundle {
     }))
 }
 
-pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32) -> impl Bundle {
+// small button of an argument row (up, down, remove)
+fn arg_row_button(
+    group: ArgGroup,
+    row: usize,
+    action: ArgRowAction,
+    font: Handle<Font>,
+) -> impl Bundle {
+    let label = match action {
+        ArgRowAction::Up => "↑",
+        ArgRowAction::Down => "↓",
+        ArgRowAction::Remove => "x",
+    };
+    (
+        Button,
+        ArgRowButton { group, row, action },
+        Node {
+            width: Val::Px(24.0),
+            height: Val::Px(24.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        BorderRadius::all(Val::Px(5.0)),
+        BackgroundColor(Color::srgb_u8(16, 16, 16)),
+        children![(
+            Text::new(label),
+            TextFont {
+                font,
+                font_size: 12.0,
+                ..default()
+            },
+            TextColor(Color::srgb(0.9, 0.9, 0.9)),
+        )],
+    )
+}
+
+pub fn arguments_panel(
+    args: &Vec<ArgKeyValue>,
+    font: Handle<Font>,
+    group: ArgGroup,
+) -> impl Bundle {
     let font2 = font.clone();
     Children::spawn((
         // show arguments
@@ -91,7 +158,8 @@ pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32)
             args.clone()
                 .into_iter()
                 .enumerate()
-                .map(move |(_index, arg)| {
+                .map(move |(index, arg)| {
+                    let font = font.clone();
                     //Text::new(arg.key.clone())
                     (
                         Node {
@@ -104,9 +172,14 @@ pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32)
                             align_items: AlignItems::Center,
                             ..default()
                         },
-                        children![
-                            (
-                                // argument flag layout
+                        Children::spawn(SpawnWith(move |p: &mut ChildSpawner| {
+                            // argument flag layout
+                            p.spawn((
+                                ArgField {
+                                    group,
+                                    row: index,
+                                    kind: ArgFieldKind::Key,
+                                },
                                 Node {
                                     width: Val::Percent(100.0),
                                     height: Val::Px(30.0),
@@ -118,6 +191,7 @@ pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32)
                                 },
                                 BorderRadius::all(Val::Px(5.0)),
                                 BackgroundColor(Color::srgb_u8(0, 0, 255).with_alpha(0.1)),
+                                TabIndex(0),
                                 // the text of flag
                                 children![(
                                     Text::new(arg.key.clone()),
@@ -128,9 +202,15 @@ pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32)
                                     },
                                     TextColor(Color::srgb(0.9, 0.9, 0.9)),
                                 )],
-                            ),
+                            ))
+                            .observe(focus_on_click);
                             // argument value layout
-                            (
+                            p.spawn((
+                                ArgField {
+                                    group,
+                                    row: index,
+                                    kind: ArgFieldKind::Value,
+                                },
                                 Node {
                                     width: Val::Percent(100.0),
                                     height: Val::Px(30.0),
@@ -142,6 +222,7 @@ pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32)
                                 },
                                 BorderRadius::all(Val::Px(5.0)),
                                 BackgroundColor(Color::WHITE.with_alpha(0.1)),
+                                TabIndex(0),
                                 // the text of value
                                 children![(
                                     Text::new(arg.value.clone()),
@@ -152,8 +233,23 @@ pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32)
                                     },
                                     TextColor(Color::srgb(0.9, 0.9, 0.9)),
                                 )],
-                            ),
-                        ],
+                            ))
+                            .observe(focus_on_click);
+                            // reorder and remove
+                            p.spawn(arg_row_button(group, index, ArgRowAction::Up, font.clone()));
+                            p.spawn(arg_row_button(
+                                group,
+                                index,
+                                ArgRowAction::Down,
+                                font.clone(),
+                            ));
+                            p.spawn(arg_row_button(
+                                group,
+                                index,
+                                ArgRowAction::Remove,
+                                font.clone(),
+                            ));
+                        })),
                         // row background color
                         // BackgroundColor(Color::WHITE.with_alpha(0.1)),
                     )
@@ -184,7 +280,7 @@ pub fn arguments_panel(args: &Vec<ArgKeyValue>, font: Handle<Font>, _group: i32)
                 },
                 //TabGroup::new(group),
                 TabGroup::modal(),
-                text_input_panel(font2.clone()),
+                text_input_panel(font2.clone(), group),
             )],
         )),
     ))
@@ -0,0 +1,79 @@
+use bevy::prelude::*;
+
+use crate::define::FfmpegArg;
+
+pub const USAGE: &str = "\
+usage: ffui --headless [--preset NAME] [--parallel N] [--replace] [FILE ...]
+
+  --preset NAME   the preset of presets.json (default: the first one)
+  --parallel N    how many ffmpeg processes run at the same time (default: 1)
+  --replace       replace the sources with the encoded files when they are done
+  FILE            files to transcode, read from stdin (one per line) when none or `-`
+
+progress is printed to stdout as JSON lines, messages go to stderr";
+
+// the options of the headless mode
+#[derive(Debug, Clone, Resource)]
+pub struct CliOptions {
+    pub files: Vec<String>,
+    pub preset: Option<String>,
+    pub parallel: usize,
+    pub replace: bool,
+}
+
+// None when ffui runs with a window
+pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Option<CliOptions>, String> {
+    let args: Vec<String> = args.into_iter().collect();
+    if !args.iter().any(|arg| arg == "--headless") {
+        return Ok(None);
+    }
+    let mut args = args.into_iter();
+    let mut options = CliOptions {
+        files: vec![],
+        preset: None,
+        parallel: 1,
+        replace: false,
+    };
+    let mut stdin = false;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--headless" => {}
+            "--preset" => {
+                options.preset = Some(args.next().ok_or("--preset needs a name")?);
+            }
+            "--parallel" => {
+                let value = args.next().ok_or("--parallel needs a number")?;
+                options.parallel = match value.parse() {
+                    Ok(count) if count > 0 => count,
+                    _ => return Err(format!("invalid --parallel: {}", value)),
+                };
+            }
+            "--replace" => options.replace = true,
+            "-" => stdin = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option: {}", flag)),
+            _ => options.files.push(arg),
+        }
+    }
+    if stdin || options.files.is_empty() {
+        for line in std::io::stdin().lines() {
+            let line = line.map_err(|e| format!("failed to read stdin: {}", e))?;
+            let line = line.trim();
+            if !line.is_empty() && !options.files.iter().any(|file| file == line) {
+                options.files.push(line.to_string());
+            }
+        }
+    }
+    Ok(Some(options))
+}
+
+// a misspelled --preset would fail every file, it is refused before anything is queued
+pub fn check_preset(options: &CliOptions, ffmpeg_arg: &FfmpegArg) -> Result<(), String> {
+    match &options.preset {
+        Some(preset) if ffmpeg_arg.preset(preset).is_none() => Err(format!(
+            "unknown preset: {} (presets: {})",
+            preset,
+            ffmpeg_arg.preset_names().join(", ")
+        )),
+        _ => Ok(()),
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::math::Vec2;
+use image::{Rgba, RgbaImage, imageops};
+
+use super::ffmpeg::frame_ffmpeg_command;
+use super::sheet::run_ffmpeg;
+use crate::define::CompareMode;
+
+// the frame at `secs`, scaled to `height` when it is set
+pub fn extract_frame(path: &str, secs: f64, height: Option<u32>) -> Result<RgbaImage, String> {
+    let png = run_ffmpeg(frame_ffmpeg_command(path, secs, height))?;
+    image::load_from_memory(&png)
+        .map(|image| image.to_rgba8())
+        .map_err(|e| format!("{}: {}", path, e))
+}
+
+// the frame at `secs` of the output and the same moment of the source, `offset` seconds
+// later when the output is trimmed, the output is scaled to the size of the source
+pub fn extract_pair(
+    source: &str,
+    output: &str,
+    secs: f64,
+    offset: f64,
+) -> Result<(RgbaImage, RgbaImage), String> {
+    let a = extract_frame(source, secs + offset, None)?;
+    let mut b = extract_frame(output, secs, None)?;
+    if a.dimensions() != b.dimensions() {
+        b = imageops::resize(&b, a.width(), a.height(), imageops::FilterType::Triangle);
+    }
+    Ok((a, b))
+}
+
+// the part of the frame that is shown, (x, y, width, height) in pixels of the source
+// `center` is relative to the frame (0..1)
+pub fn view_rect(width: u32, height: u32, zoom: f32, center: Vec2) -> (u32, u32, u32, u32) {
+    let zoom = zoom.max(1.0);
+    let w = ((width as f32 / zoom) as u32).max(1);
+    let h = ((height as f32 / zoom) as u32).max(1);
+    let x = (center.x * width as f32 - w as f32 / 2.0).clamp(0.0, (width - w) as f32) as u32;
+    let y = (center.y * height as f32 - h as f32 / 2.0).clamp(0.0, (height - h) as f32) as u32;
+    (x, y, w, h)
+}
+
+// the image of the comparison view
+// Split: the source left of `split` (0..1 of the frame width) and the output right of it
+// SideBySide: the same part of both frames next to each other
+pub fn compose(
+    a: &RgbaImage,
+    b: &RgbaImage,
+    mode: CompareMode,
+    split: f32,
+    zoom: f32,
+    center: Vec2,
+) -> RgbaImage {
+    let (x, y, w, h) = view_rect(a.width(), a.height(), zoom, center);
+    let crop_a = imageops::crop_imm(a, x, y, w, h).to_image();
+    let crop_b = imageops::crop_imm(b, x, y, w, h).to_image();
+    match mode {
+        CompareMode::Split => {
+            let split_x = (split.clamp(0.0, 1.0) * a.width() as f32) as u32;
+            let mut view = crop_b;
+            for (px, py, pixel) in crop_a.enumerate_pixels() {
+                if x + px < split_x {
+                    view.put_pixel(px, py, *pixel);
+                }
+            }
+            // the split line
+            if (x..x + w).contains(&split_x) {
+                let line = split_x - x;
+                let line_width = (2.0 * w as f32 / 1280.0).ceil().max(1.0) as u32;
+                for lx in line..(line + line_width).min(w) {
+                    for ly in 0..h {
+                        view.put_pixel(lx, ly, Rgba([255, 255, 255, 255]));
+                    }
+                }
+            }
+            view
+        }
+        CompareMode::SideBySide => {
+            let gap = (w / 100).max(2);
+            let mut view = RgbaImage::from_pixel(w * 2 + gap, h, Rgba([16, 16, 16, 255]));
+            imageops::overlay(&mut view, &crop_a, 0, 0);
+            imageops::overlay(&mut view, &crop_b, (w + gap) as i64, 0);
+            view
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn whole_frame_without_zoom() {
+        assert_eq!(
+            view_rect(1920, 1080, 1.0, Vec2::new(0.5, 0.5)),
+            (0, 0, 1920, 1080)
+        );
+        // zooming out is not possible
+        assert_eq!(
+            view_rect(1920, 1080, 0.5, Vec2::new(0.2, 0.9)),
+            (0, 0, 1920, 1080)
+        );
+    }
+
+    #[test]
+    fn zoomed_view_around_the_center() {
+        assert_eq!(
+            view_rect(1920, 1080, 2.0, Vec2::new(0.5, 0.5)),
+            (480, 270, 960, 540)
+        );
+        assert_eq!(
+            view_rect(1920, 1080, 4.0, Vec2::new(0.25, 0.75)),
+            (240, 675, 480, 270)
+        );
+    }
+
+    #[test]
+    fn zoomed_view_stays_in_the_frame() {
+        assert_eq!(
+            view_rect(1920, 1080, 2.0, Vec2::new(0.0, 0.0)),
+            (0, 0, 960, 540)
+        );
+        assert_eq!(
+            view_rect(1920, 1080, 2.0, Vec2::new(1.0, 1.0)),
+            (960, 540, 960, 540)
+        );
+        // at least one pixel
+        assert_eq!(
+            view_rect(1920, 1080, 10000.0, Vec2::new(0.5, 0.5)),
+            (959, 539, 1, 1)
+        );
+    }
+}
@@ -5,20 +5,116 @@ use std::path::Path;
 use std::process::Stdio;
 use tokio::process::Command;
 
-use crate::define::ArgKeyValue;
+use crate::define::{
+    ArgKeyValue, HwBackend, MediaInfo, QualityMetric, parse_encoders, parse_hwaccels,
+};
+
+// the binaries can be replaced (e.g. by a fake script) with FFUI_FFMPEG and FFUI_FFPROBE
+pub fn ffmpeg_bin() -> String {
+    std::env::var("FFUI_FFMPEG").unwrap_or_else(|_| "ffmpeg".to_string())
+}
+
+pub fn ffprobe_bin() -> String {
+    std::env::var("FFUI_FFPROBE").unwrap_or_else(|_| "ffprobe".to_string())
+}
+
+// run `ffmpeg -hide_banner <flag>` and return stdout
+fn ffmpeg_list(flag: &str) -> Result<String, String> {
+    let output = std::process::Command::new(ffmpeg_bin())
+        .args(["-hide_banner", flag])
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", ffmpeg_bin(), e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "{} {} exited with {}",
+            ffmpeg_bin(),
+            flag,
+            output.status
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+// whether this ffmpeg build has a filter (e.g. libvmaf)
+pub fn has_filter(name: &str) -> bool {
+    ffmpeg_list("-filters").is_ok_and(|list| {
+        list.lines()
+            .any(|line| line.split_whitespace().nth(1) == Some(name))
+    })
+}
+
+// media information of a file, blocking
+pub fn probe_media(path: &Path) -> Result<MediaInfo, String> {
+    let output = std::process::Command::new(ffprobe_bin())
+        .args([
+            "-v",
+            "error",
+            "-print_format",
+            "json",
+            "-show_format",
+            "-show_streams",
+        ])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| format!("Failed to run {}: {}", ffprobe_bin(), e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    MediaInfo::from_ffprobe(&String::from_utf8_lossy(&output.stdout)).map_err(|e| e.to_string())
+}
+
+// join the parts of one encode into `output` without encoding them again, blocking
+// the concat demuxer reads the part names from a list file next to the output
+pub fn concat_parts(parts: &[&Path], output: &Path) -> Result<(), String> {
+    let mut list = output.as_os_str().to_owned();
+    list.push(".txt");
+    let list = std::path::PathBuf::from(list);
+    let content: String = parts
+        .iter()
+        .map(|part| {
+            let path = std::path::absolute(part).unwrap_or_else(|_| part.to_path_buf());
+            format!("file '{}'\n", path.to_string_lossy().replace('\'', "'\\''"))
+        })
+        .collect();
+    std::fs::write(&list, content)
+        .map_err(|e| format!("Failed to write {}: {}", list.display(), e))?;
+    let result = std::process::Command::new(ffmpeg_bin())
+        .args([
+            "-hide_banner",
+            "-v",
+            "error",
+            "-f",
+            "concat",
+            "-safe",
+            "0",
+            "-i",
+        ])
+        .arg(&list)
+        .args(["-map", "0", "-c", "copy", "-y"])
+        .arg(output)
+        .stdin(Stdio::null())
+        .output();
+    let _ = std::fs::remove_file(&list);
+    let output = result.map_err(|e| format!("Failed to run {}: {}", ffmpeg_bin(), e))?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(())
+}
+
+// the hardware backend usable by this ffmpeg build and the names of all its encoders
+pub fn probe_hardware() -> Result<(Option<HwBackend>, Vec<String>), String> {
+    let hwaccels = parse_hwaccels(&ffmpeg_list("-hwaccels")?);
+    let encoders = parse_encoders(&ffmpeg_list("-encoders")?);
+    Ok((HwBackend::choose(&hwaccels, &encoders), encoders))
+}
 
 // -hwaccel
 // videotoolbox(MacOs)
-pub fn create_ffmpeg_command(path: String, args: &Vec<ArgKeyValue>) -> Command {
-    let Some(filename) = Path::new(&path)
-        .file_stem()
-        .and_then(|name| name.to_str())
-        .map(|name_str| format!("{}.mp4", name_str))
-    else {
-        panic!("Invalid file path: {}", path);
-    };
-
-    let mut cmd = Command::new("ffmpeg");
+pub fn create_ffmpeg_command(path: String, output: &Path, args: &Vec<ArgKeyValue>) -> Command {
+    let mut cmd = Command::new(ffmpeg_bin());
     // cmd.arg("-hwaccel")
     //     .arg("videotoolbox")
     //     .arg("-nostdin")
@@ -39,79 +135,84 @@ pub fn create_ffmpeg_command(path: String, args: &Vec<ArgKeyValue>) -> Command {
     //     .arg(filename)
     //     .stdout(Stdio::piped())
     //     .stderr(Stdio::piped());
-    cmd.args(["-i", &path, "-y", &filename]);
-    for arg in args {
+    // -hwaccel options, the vaapi device and the in/out points (fast seek) belong to the input,
+    // so they go before -i
+    let (input_args, output_args): (Vec<_>, Vec<_>) = args.iter().partition(|arg| {
+        arg.key.starts_with("-hwaccel")
+            || arg.key == "-vaapi_device"
+            || arg.key == "-ss"
+            || arg.key == "-to"
+    });
+    for arg in input_args {
         cmd.arg(&arg.key);
         if !arg.value.is_empty() {
             cmd.arg(&arg.value);
         }
     }
-    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
-    cmd
-}
-
-// software encoding
-// libx265
-pub fn create_ffmpeg_command_libx265(path: String, args: &Vec<ArgKeyValue>) -> Command {
-    let Some(filename) = Path::new(&path)
-        .file_stem()
-        .and_then(|name| name.to_str())
-        .map(|name_str| format!("{}.mp4", name_str))
-    else {
-        panic!("Invalid file path: {}", path);
-    };
-
-    let mut cmd = Command::new("ffmpeg");
-    // cmd.arg("-nostdin")
-    //     .arg("-i")
-    //     .arg(path) // 替换为你的命令
-    //     .args(["-vf", "scale=-2:720,fps=30"])
-    //     .arg("-c:v")
-    //     .arg("libx265")
-    //     .args(["-quality", "high"])
-    //     .args(["-b:v", "2000k"])
-    //     .args(["-maxrate", "8000k"])
-    //     .args(["-bufsize", "16000k"])
-    //     .args(["-c:a", "copy"])
-    //     .args(["-tag:v", "hvc1"])
-    //     .args(["-loglevel", "info"])
-    //     .args(["-progress", "pipe:1"])
-    //     .arg("-y")
-    //     .arg(filename) // 覆盖输出文件
-    //     .stdout(Stdio::piped())
-    //     .stderr(Stdio::piped());
-    cmd.args(["-i", &path, "-y", &filename]);
-    for arg in args {
+    cmd.args(["-i", &path, "-y"]);
+    // ffmpeg only keeps the last -vf, so the filters (e.g. the crop of an edit) form one chain
+    let filters: Vec<&str> = output_args
+        .iter()
+        .filter(|arg| arg.key == "-vf")
+        .map(|arg| arg.value.as_str())
+        .collect();
+    let mut filters_added = false;
+    for arg in output_args {
+        if arg.key == "-vf" {
+            if !filters_added {
+                cmd.args(["-vf", &filters.join(",")]);
+                filters_added = true;
+            }
+            continue;
+        }
         cmd.arg(&arg.key);
         if !arg.value.is_empty() {
             cmd.arg(&arg.value);
         }
     }
+    cmd.arg(output);
     cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
     cmd
 }
 
+// the null muxer still needs an output path
+const NULL_OUTPUT: &str = if cfg!(windows) { "NUL" } else { "/dev/null" };
+
+// the two runs of a two-pass encode, the first one only writes the statistics to `passlog`
+pub fn two_pass_commands(
+    path: &str,
+    output: &Path,
+    args: &[ArgKeyValue],
+    passlog: &Path,
+) -> [Command; 2] {
+    let passlog = passlog.to_string_lossy();
+    let mut first = args.to_vec();
+    first.extend([
+        ArgKeyValue::new("-pass", "1"),
+        ArgKeyValue::new("-passlogfile", &passlog),
+        ArgKeyValue::new("-an", ""),
+        ArgKeyValue::new("-f", "null"),
+    ]);
+    let mut second = args.to_vec();
+    second.extend([
+        ArgKeyValue::new("-pass", "2"),
+        ArgKeyValue::new("-passlogfile", &passlog),
+    ]);
+    [
+        create_ffmpeg_command(path.to_string(), Path::new(NULL_OUTPUT), &first),
+        create_ffmpeg_command(path.to_string(), output, &second),
+    ]
+}
+
 // snapshot
-pub fn snapshot_ffmpeg_command(
-    path: String,
-    source: bool,
-    total_secs: u64,
-    args: &Vec<ArgKeyValue>,
-) -> Command {
-    let filename = if !source {
-        let Some(filename) = Path::new(&path)
-            .file_stem()
-            .and_then(|name| name.to_str())
-            .map(|name_str| format!("{}.mp4", name_str))
-        else {
-            panic!("Invalid file path: {}", path);
-        };
-        filename
+// `path` is the source or the output of a task
+pub fn snapshot_ffmpeg_command(path: String, total_secs: u64, args: &Vec<ArgKeyValue>) -> Command {
+    // rand a second of total duration, the first frame of a clip shorter than 2 seconds
+    let second = if total_secs > 1 {
+        rand::random_range(1..total_secs)
     } else {
-        path
+        0
     };
-    // rand a second of total duration
-    let second = rand::random_range(1..total_secs);
     // format second to hh:mm:ss
     let second_str = format!(
         "{:02}:{:02}:{:02}.000",
@@ -120,9 +221,9 @@ pub fn snapshot_ffmpeg_command(
         second % 60
     );
 
-    let mut cmd = Command::new("ffmpeg");
+    let mut cmd = Command::new(ffmpeg_bin());
     // the snapshot arguments order is important
-    cmd.args(["-ss", &second_str, "-i", &filename]);
+    cmd.args(["-ss", &second_str, "-i", &path]);
     for arg in args {
         cmd.arg(&arg.key);
         if !arg.value.is_empty() {
@@ -133,9 +234,45 @@ pub fn snapshot_ffmpeg_command(
     cmd
 }
 
+// one frame at `secs` as png on stdout, scaled to `height` or in full size
+pub fn frame_ffmpeg_command(path: &str, secs: f64, height: Option<u32>) -> Command {
+    let mut cmd = Command::new(ffmpeg_bin());
+    cmd.args([
+        "-hide_banner",
+        "-v",
+        "error",
+        "-ss",
+        &format!("{:.3}", secs),
+        "-i",
+        path,
+    ])
+    .args(["-frames:v", "1"]);
+    if let Some(height) = height {
+        cmd.args(["-vf", &format!("scale=-2:{}", height)]);
+    }
+    cmd.args(["-vcodec", "png", "-f", "image2pipe", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
+// up to `count` frames where the scene changes more than `threshold` (0..1), png on stdout
+pub fn scene_ffmpeg_command(path: &str, threshold: f64, count: usize, height: u32) -> Command {
+    let filter = format!("select='gt(scene,{})',scale=-2:{}", threshold, height);
+    let mut cmd = Command::new(ffmpeg_bin());
+    cmd.args(["-hide_banner", "-v", "error", "-i", path, "-vf", &filter])
+        .args(["-fps_mode", "vfr", "-frames:v", &count.to_string()])
+        .args(["-vcodec", "png", "-f", "image2pipe", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    cmd
+}
+
 // analyze
 pub fn analyze_ffprobe_command(path: String, args: &Vec<ArgKeyValue>) -> Command {
-    let mut cmd = Command::new("ffprobe");
+    let mut cmd = Command::new(ffprobe_bin());
     for arg in args {
         cmd.arg(&arg.key);
         if !arg.value.is_empty() {
@@ -154,3 +291,96 @@ pub fn analyze_ffprobe_command(path: String, args: &Vec<ArgKeyValue>) -> Command
     cmd.arg(path).stdout(Stdio::piped()).stderr(Stdio::piped());
     cmd
 }
+
+// compare the output with the source, the score is printed on stderr
+// both inputs are re-timed to the frame rate of the output and start at 0, so the frames
+// line up, then the output (the distorted input) is scaled to the size of the source
+pub fn quality_ffmpeg_command(
+    source: &str,
+    output: &str,
+    metric: QualityMetric,
+    frame_rate: f64,
+) -> Command {
+    let filter = match metric {
+        QualityMetric::Ssim => "ssim",
+        QualityMetric::Psnr => "psnr",
+        QualityMetric::Vmaf => "libvmaf",
+    };
+    let align = format!("fps={:.6},settb=AVTB,setpts=PTS-STARTPTS", frame_rate);
+    let lavfi = format!(
+        "[0:v]{align}[out];[1:v]{align}[src];\
+         [out][src]scale2ref=flags=bicubic[main][ref];[main][ref]{filter}"
+    );
+    let mut cmd = Command::new(ffmpeg_bin());
+    cmd.args(["-hide_banner", "-nostats", "-i", output, "-i", source])
+        .args(["-lavfi", &lavfi, "-an", "-f", "null", "-"])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped());
+    cmd
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::define::{FfmpegArg, software_args, vaapi_device};
+    use crate::testing::{fake_ffmpeg, pairs};
+
+    fn probe_fake(hwaccels: &str, encoders: &str) -> (Option<HwBackend>, Vec<String>) {
+        let _guard = fake_ffmpeg(hwaccels, encoders);
+        probe_hardware().unwrap()
+    }
+
+    #[test]
+    fn parses_the_fake_lists() {
+        let _guard = fake_ffmpeg("vdpau cuda", "hevc_nvenc");
+        assert_eq!(
+            parse_hwaccels(&ffmpeg_list("-hwaccels").unwrap()),
+            ["vdpau", "cuda"]
+        );
+        assert_eq!(
+            parse_encoders(&ffmpeg_list("-encoders").unwrap()),
+            ["libx264", "libx265", "hevc_nvenc", "aac"]
+        );
+    }
+
+    #[test]
+    fn chooses_the_first_complete_backend() {
+        // vaapi is listed first, but nvenc comes first in the order of preference
+        let (backend, _) = probe_fake("vaapi cuda", "hevc_vaapi hevc_nvenc");
+        assert_eq!(backend, Some(HwBackend::Nvenc));
+        // a hwaccel without its encoder is skipped
+        let (backend, _) = probe_fake("cuda vaapi", "hevc_vaapi");
+        assert_eq!(backend, Some(HwBackend::Vaapi));
+        let (backend, _) = probe_fake("videotoolbox", "hevc_videotoolbox");
+        assert_eq!(backend, Some(HwBackend::VideoToolbox));
+    }
+
+    #[test]
+    fn falls_back_to_software() {
+        let (backend, encoders) = probe_fake("cuda", "");
+        assert_eq!(backend, None);
+        let mut ffmpeg_args = FfmpegArg::default();
+        let message = ffmpeg_args.apply_hardware(backend, &encoders).unwrap();
+        assert!(message.starts_with("No hardware encoder found"));
+        for preset in ffmpeg_args.presets.iter().filter(|preset| preset.hardware) {
+            assert_eq!(pairs(&preset.args), pairs(&software_args()));
+        }
+    }
+
+    #[test]
+    fn rebuilds_hardware_presets_for_the_backend() {
+        let (backend, encoders) = probe_fake("vaapi", "hevc_vaapi");
+        let mut ffmpeg_args = FfmpegArg::default();
+        ffmpeg_args.apply_hardware(backend, &encoders).unwrap();
+        for preset in ffmpeg_args.presets.iter().filter(|preset| preset.hardware) {
+            assert_eq!(pairs(&preset.args), pairs(&HwBackend::Vaapi.args()));
+            assert!(
+                preset
+                    .args
+                    .iter()
+                    .any(|arg| arg.key == "-vaapi_device" && arg.value == vaapi_device())
+            );
+        }
+    }
+}
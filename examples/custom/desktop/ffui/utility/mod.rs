@@ -1,5 +1,14 @@
+pub mod cli;
+pub mod compare;
 pub mod ffmpeg;
+pub mod platform;
+pub mod quality;
+pub mod replace;
+pub mod session;
+pub mod sheet;
+pub mod sidecar;
 pub mod task;
 pub mod time;
+pub mod watch;
 
 pub use ffmpeg::*;
@@ -0,0 +1,124 @@
+use arboard::Clipboard;
+use once_cell::sync::Lazy;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::Mutex;
+use tokio::sync::mpsc;
+
+// the file manager command of each platform
+#[cfg(target_os = "macos")]
+fn open_command(path: &Path) -> Command {
+    let mut cmd = Command::new("open");
+    cmd.arg(path);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+fn open_command(path: &Path) -> Command {
+    let mut cmd = Command::new("explorer");
+    cmd.arg(path);
+    cmd
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn open_command(path: &Path) -> Command {
+    let mut cmd = Command::new("xdg-open");
+    cmd.arg(path);
+    cmd
+}
+
+// select the file in the file manager
+#[cfg(target_os = "macos")]
+fn reveal_command(path: &Path) -> Command {
+    let mut cmd = Command::new("open");
+    cmd.arg("-R").arg(path);
+    cmd
+}
+
+#[cfg(target_os = "windows")]
+fn reveal_command(path: &Path) -> Command {
+    let mut cmd = Command::new("explorer");
+    cmd.arg(format!("/select,{}", path.display()));
+    cmd
+}
+
+// xdg-open can not select a file, open the directory that contains it
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+fn reveal_command(path: &Path) -> Command {
+    open_command(path.parent().unwrap_or(path))
+}
+
+// the per-user directory of the application data
+#[cfg(target_os = "macos")]
+pub fn data_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME")?;
+    Some(PathBuf::from(home).join("Library/Application Support/ffui"))
+}
+
+#[cfg(target_os = "windows")]
+pub fn data_dir() -> Option<PathBuf> {
+    let app_data = std::env::var_os("APPDATA")?;
+    Some(PathBuf::from(app_data).join("ffui"))
+}
+
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+pub fn data_dir() -> Option<PathBuf> {
+    let data_home = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| Some(PathBuf::from(std::env::var_os("HOME")?).join(".local/share")))?;
+    Some(data_home.join("ffui"))
+}
+
+// run the command without blocking the ui, failures go to the toast channel
+fn spawn_reported(mut cmd: Command, toast_tx: mpsc::Sender<String>) {
+    let program = cmd.get_program().to_string_lossy().to_string();
+    let mut child = match cmd.spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            let _ = toast_tx.try_send(format!("Failed to run {}: {}", program, e));
+            return;
+        }
+    };
+    std::thread::spawn(move || match child.wait() {
+        // explorer exits with 1 even when it succeeds
+        Ok(status) if status.success() || cfg!(target_os = "windows") => {}
+        Ok(status) => {
+            let _ = toast_tx.blocking_send(format!("{} exited with {}", program, status));
+        }
+        Err(e) => {
+            let _ = toast_tx.blocking_send(format!("Failed to wait {}: {}", program, e));
+        }
+    });
+}
+
+// open a directory (or a file) with the default application
+pub fn open_path(path: &Path, toast_tx: mpsc::Sender<String>) {
+    if !path.exists() {
+        let _ = toast_tx.try_send(format!("Not found: {}", path.display()));
+        return;
+    }
+    spawn_reported(open_command(path), toast_tx);
+}
+
+// show a file in the file manager
+pub fn reveal_file(path: &Path, toast_tx: mpsc::Sender<String>) {
+    if !path.exists() {
+        let _ = toast_tx.try_send(format!("Not found: {}", path.display()));
+        return;
+    }
+    spawn_reported(reveal_command(path), toast_tx);
+}
+
+// on linux the copied text is served by the clipboard instance, so keep it alive
+static CLIPBOARD: Lazy<Mutex<Option<Clipboard>>> = Lazy::new(|| Mutex::new(None));
+
+pub fn copy_to_clipboard(text: &str) -> Result<(), String> {
+    let mut clipboard = CLIPBOARD.lock().map_err(|e| e.to_string())?;
+    if clipboard.is_none() {
+        *clipboard = Some(Clipboard::new().map_err(|e| e.to_string())?);
+    }
+    let Some(clipboard) = clipboard.as_mut() else {
+        return Err("clipboard is not available".to_string());
+    };
+    clipboard.set_text(text).map_err(|e| e.to_string())
+}
@@ -0,0 +1,125 @@
+use std::path::Path;
+use std::sync::OnceLock;
+
+use super::ffmpeg::{has_filter, probe_media, quality_ffmpeg_command};
+use crate::TOKIO_RT;
+use crate::define::QualityMetric;
+
+// VMAF falls back to SSIM when the ffmpeg build has no libvmaf
+fn usable_metric(metric: QualityMetric) -> QualityMetric {
+    static LIBVMAF: OnceLock<bool> = OnceLock::new();
+    if metric == QualityMetric::Vmaf && !*LIBVMAF.get_or_init(|| has_filter("libvmaf")) {
+        QualityMetric::Ssim
+    } else {
+        metric
+    }
+}
+
+// the score of the output compared with the source, blocking
+pub fn measure_quality(
+    source: &str,
+    output: &str,
+    metric: QualityMetric,
+) -> Result<(QualityMetric, f64), String> {
+    let metric = usable_metric(metric);
+    let frame_rate = aligned_frame_rate(source, output)?;
+    let result = TOKIO_RT
+        .block_on(quality_ffmpeg_command(source, output, metric, frame_rate).output())
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    let stderr = String::from_utf8_lossy(&result.stderr);
+    if !result.status.success() {
+        return Err(stderr.lines().last().unwrap_or("ffmpeg failed").to_string());
+    }
+    parse_score(&stderr, metric)
+        .map(|score| (metric, score))
+        .ok_or_else(|| format!("no {} score in the ffmpeg output", metric.name()))
+}
+
+// the frame rate both files are compared at, the one of the output
+// Err when the frames of the output can not be matched with the frames of the source
+fn aligned_frame_rate(source: &str, output: &str) -> Result<f64, String> {
+    let source = probe_media(Path::new(source))?;
+    let output = probe_media(Path::new(output))?;
+    let frame_rate = output.video.as_ref().map_or(0.0, |video| video.frame_rate);
+    if frame_rate <= 0.0 || source.video.is_none() {
+        return Err("no frame rate to align the frames".to_string());
+    }
+    frames_aligned(source.duration, output.duration, frame_rate)?;
+    Ok(frame_rate)
+}
+
+// the largest difference in frames between the source and the output,
+// a few frames are lost to the rounding of the re-timing
+const MAX_FRAME_DIFFERENCE: f64 = 2.0;
+
+fn frames_aligned(source_secs: f64, output_secs: f64, frame_rate: f64) -> Result<(), String> {
+    let source_frames = (source_secs * frame_rate).round();
+    let output_frames = (output_secs * frame_rate).round();
+    if (source_frames - output_frames).abs() > MAX_FRAME_DIFFERENCE {
+        return Err(format!(
+            "{} frames in the output, {} in the source",
+            output_frames, source_frames
+        ));
+    }
+    Ok(())
+}
+
+// the summary line of each filter:
+// [Parsed_ssim_1 @ 0x..] SSIM Y:0.98 (17.1) U:0.99 (20.3) V:0.99 (20.1) All:0.987 (18.9)
+// [Parsed_psnr_1 @ 0x..] PSNR y:41.2 u:45.3 v:45.8 average:42.4 min:38.1 max:47.9
+// [Parsed_libvmaf_1 @ 0x..] VMAF score: 94.3
+fn parse_score(stderr: &str, metric: QualityMetric) -> Option<f64> {
+    let marker = match metric {
+        QualityMetric::Ssim => "All:",
+        QualityMetric::Psnr => "average:",
+        QualityMetric::Vmaf => "VMAF score:",
+    };
+    stderr.lines().rev().find_map(|line| {
+        let rest = line.split(marker).nth(1)?;
+        rest.split_whitespace().next()?.parse().ok()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SSIM: &str = "\
+frame=  300 fps=120 q=-0.0 Lsize=N/A time=00:00:10.00 bitrate=N/A speed=4.01x
+[Parsed_ssim_6 @ 0x600001b2c000] SSIM Y:0.981204 (17.260187) U:0.990731 (20.330134) \
+V:0.990118 (20.049163) All:0.984392 (18.067245)";
+
+    const PSNR: &str = "\
+[Parsed_psnr_6 @ 0x600002e3c0b0] PSNR y:41.216803 u:45.304478 v:45.811267 \
+average:42.400952 min:38.117432 max:47.925102";
+
+    const VMAF: &str = "\
+[libvmaf @ 0x7f8b5c004a00] model loaded
+[Parsed_libvmaf_6 @ 0x7f8b5c0049c0] VMAF score: 94.318923";
+
+    #[test]
+    fn parses_the_summary_lines() {
+        assert_eq!(parse_score(SSIM, QualityMetric::Ssim), Some(0.984392));
+        assert_eq!(parse_score(PSNR, QualityMetric::Psnr), Some(42.400952));
+        assert_eq!(parse_score(VMAF, QualityMetric::Vmaf), Some(94.318923));
+    }
+
+    #[test]
+    fn no_score_of_another_metric() {
+        assert_eq!(parse_score(SSIM, QualityMetric::Vmaf), None);
+        assert_eq!(parse_score("", QualityMetric::Ssim), None);
+    }
+
+    #[test]
+    fn frames_of_a_retimed_output() {
+        // a 60 fps source encoded at 30 fps keeps its length
+        assert!(frames_aligned(10.0, 10.0, 30.0).is_ok());
+        // the container of the output ends a frame later
+        assert!(frames_aligned(10.0, 10.04, 30.0).is_ok());
+        // a trimmed output
+        assert_eq!(
+            frames_aligned(10.0, 4.0, 30.0),
+            Err("120 frames in the output, 300 in the source".to_string())
+        );
+    }
+}
@@ -0,0 +1,250 @@
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use thiserror::Error;
+
+use super::ffmpeg::{ffprobe_bin, probe_media};
+use super::sidecar::update_sidecars;
+use crate::define::{AppConfig, FilesState, ReplaceRecord, TaskStatus};
+
+// the originals are moved here instead of being deleted
+pub const TRASH_DIR: &str = ".ffui_trash";
+
+// the output duration may differ from the source by this many seconds, or 1%
+const DURATION_TOLERANCE: f64 = 1.0;
+
+#[derive(Debug, Error)]
+pub enum ReplaceError {
+    #[error("invalid file path: {0}")]
+    InvalidPath(String),
+    #[error("output file is missing: {0}")]
+    MissingOutput(PathBuf),
+    #[error("original file is missing from trash: {0}")]
+    MissingOriginal(PathBuf),
+    #[error("target file already exists: {0}")]
+    TargetExists(PathBuf),
+    #[error("output file is empty: {0}")]
+    EmptyOutput(PathBuf),
+    #[error("the output is split into segments: {0}")]
+    Segmented(PathBuf),
+    #[error("failed to probe {path}: {message}")]
+    Probe { path: PathBuf, message: String },
+    #[error("duration mismatch: source {source_secs:.2}s, output {output_secs:.2}s")]
+    DurationMismatch { source_secs: f64, output_secs: f64 },
+    #[error("failed to {action} {path}: {error}")]
+    Io {
+        action: &'static str,
+        path: PathBuf,
+        error: std::io::Error,
+    },
+    #[error("{error}, and the rollback failed too: {rollback}")]
+    Rollback {
+        error: Box<ReplaceError>,
+        rollback: Box<ReplaceError>,
+    },
+}
+
+impl ReplaceError {
+    pub fn io(action: &'static str, path: &Path) -> impl FnOnce(std::io::Error) -> Self {
+        let path = path.to_path_buf();
+        move |error| ReplaceError::Io {
+            action,
+            path,
+            error,
+        }
+    }
+}
+
+// duration of a media file in seconds
+fn probe_duration(path: &Path) -> Result<f64, ReplaceError> {
+    let probe_error = |message: String| ReplaceError::Probe {
+        path: path.to_path_buf(),
+        message,
+    };
+    let output = std::process::Command::new(ffprobe_bin())
+        .args(["-v", "error", "-show_entries", "format=duration"])
+        .args(["-of", "default=noprint_wrappers=1:nokey=1"])
+        .arg(path)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| probe_error(e.to_string()))?;
+    if !output.status.success() {
+        return Err(probe_error(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ));
+    }
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|e: std::num::ParseFloatError| probe_error(e.to_string()))
+}
+
+// the output must exist, not be empty and last as long as the source
+pub fn verify_output(output: &Path, source_secs: f64) -> Result<(), ReplaceError> {
+    let meta =
+        std::fs::metadata(output).map_err(|_| ReplaceError::MissingOutput(output.to_path_buf()))?;
+    if meta.len() == 0 {
+        return Err(ReplaceError::EmptyOutput(output.to_path_buf()));
+    }
+    // the source duration is unknown when it was never analyzed
+    if source_secs <= 0.0 {
+        return Ok(());
+    }
+    let output_secs = probe_duration(output)?;
+    let tolerance = DURATION_TOLERANCE.max(source_secs * 0.01);
+    if (output_secs - source_secs).abs() > tolerance {
+        return Err(ReplaceError::DurationMismatch {
+            source_secs,
+            output_secs,
+        });
+    }
+    Ok(())
+}
+
+// rename when possible, copy and remove across devices
+pub fn move_file(from: &Path, to: &Path) -> Result<(), ReplaceError> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    // copy to a temporary name first, so `to` never holds a partial file
+    let mut tmp = to.as_os_str().to_owned();
+    tmp.push(".ffui_tmp");
+    let tmp = PathBuf::from(tmp);
+    if let Err(e) = std::fs::copy(from, &tmp) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(ReplaceError::io("copy", from)(e));
+    }
+    if let Err(e) = std::fs::rename(&tmp, to) {
+        let _ = std::fs::remove_file(&tmp);
+        return Err(ReplaceError::io("rename", &tmp)(e));
+    }
+    std::fs::remove_file(from).map_err(ReplaceError::io("remove", from))
+}
+
+// write a temporary file and rename it, a crash never leaves half a file
+pub fn write_file(path: &Path, content: &str) -> Result<(), ReplaceError> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".ffui_tmp");
+    let tmp = PathBuf::from(tmp);
+    std::fs::write(&tmp, content).map_err(ReplaceError::io("write", &tmp))?;
+    std::fs::rename(&tmp, path).map_err(ReplaceError::io("rename", &tmp))
+}
+
+// a path in the trash directory that is not used yet
+pub fn trash_path(dir: &Path, name: &str) -> PathBuf {
+    let mut path = dir.join(name);
+    let mut count = 1;
+    while path.exists() {
+        path = dir.join(format!("{}.{}", name, count));
+        count += 1;
+    }
+    path
+}
+
+// replace the source with the encoded output
+// the original is moved into the trash first, and moved back when a later step fails
+// the replaced file keeps the stem of the source and the extension of the output
+pub fn replace(path: &str, output: &Path, source_secs: f64) -> Result<ReplaceRecord, ReplaceError> {
+    let source = PathBuf::from(path);
+    let invalid = || ReplaceError::InvalidPath(path.to_string());
+    let dir = source.parent().ok_or_else(invalid)?;
+    let name = source
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(invalid)?;
+    let stem = source
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .ok_or_else(invalid)?;
+    let ext = output
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .ok_or_else(invalid)?;
+    let output = output.to_path_buf();
+    let target = dir.join(format!("{}.{}", stem, ext));
+
+    verify_output(&output, source_secs)?;
+    // never overwrite another file next to the source
+    if target != source && target.exists() {
+        return Err(ReplaceError::TargetExists(target));
+    }
+
+    let trash_dir = dir.join(TRASH_DIR);
+    std::fs::create_dir_all(&trash_dir).map_err(ReplaceError::io("create", &trash_dir))?;
+    let trash = trash_path(&trash_dir, name);
+
+    move_file(&source, &trash)?;
+    if let Err(error) = move_file(&output, &target) {
+        return Err(match move_file(&trash, &source) {
+            Ok(()) => error,
+            Err(rollback) => ReplaceError::Rollback {
+                error: Box::new(error),
+                rollback: Box::new(rollback),
+            },
+        });
+    }
+
+    Ok(ReplaceRecord {
+        source,
+        trash,
+        target,
+        output,
+        sidecars: vec![],
+    })
+}
+
+// replace the source of a Done line and update its sidecars
+// returns the sidecar failures, the replace itself succeeded
+pub fn replace_line(
+    state: &mut FilesState,
+    index: usize,
+    config: &AppConfig,
+) -> Result<Vec<String>, ReplaceError> {
+    let Some(path) = state.lines.get(index).cloned() else {
+        return Err(ReplaceError::InvalidPath(format!("line {}", index + 1)));
+    };
+    let Some(output) = state.outputs.get(&index).cloned() else {
+        return Err(ReplaceError::MissingOutput(PathBuf::from(&path)));
+    };
+    if state.segments.contains_key(&index) {
+        return Err(ReplaceError::Segmented(output));
+    }
+    let source_secs = match state.media.get(&index) {
+        Some(info) => state.output_secs(index, info.duration),
+        // the total of a trimmed line is already the length of the output
+        None => state.progress.get(&index).map_or(0.0, |p| p.total as f64),
+    };
+    let mut record = replace(&path, &output, source_secs)?;
+    // the sidecars describe the new file
+    let warnings = match probe_media(&record.target) {
+        Ok(info) => update_sidecars(&mut record, config.sidecars(&path), &info),
+        Err(e) => vec![format!("Sidecars not updated: {}", e)],
+    };
+    state.status[index] = TaskStatus::Replaced;
+    state.replaced.insert(index, record);
+    Ok(warnings)
+}
+
+// put the original back, the encoded file returns to where it was written
+// returns the sidecars that could not be restored, the undo itself succeeded
+pub fn undo_replace(record: &ReplaceRecord) -> Result<Vec<String>, ReplaceError> {
+    if !record.trash.exists() {
+        return Err(ReplaceError::MissingOriginal(record.trash.clone()));
+    }
+    move_file(&record.target, &record.output)?;
+    if let Err(error) = move_file(&record.trash, &record.source) {
+        return Err(match move_file(&record.output, &record.target) {
+            Ok(()) => error,
+            Err(rollback) => ReplaceError::Rollback {
+                error: Box::new(error),
+                rollback: Box::new(rollback),
+            },
+        });
+    }
+    let mut warnings = vec![];
+    for (path, backup) in &record.sidecars {
+        if let Err(e) = move_file(backup, path) {
+            warnings.push(format!("Failed to restore {}: {}", path.display(), e));
+        }
+    }
+    Ok(warnings)
+}
@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+use std::path::PathBuf;
+
+use super::ffmpeg::probe_media;
+use super::platform::data_dir;
+use super::replace::{verify_output, write_file};
+use crate::define::{FilesState, TaskStatus};
+
+// the autosaved FilesState in the data directory
+pub const SESSION_FILE: &str = "session.json";
+
+pub fn session_path() -> Option<PathBuf> {
+    Some(data_dir()?.join(SESSION_FILE))
+}
+
+// write the state to a temporary file and rename it, a crash keeps the previous session
+pub fn save_session(state: &FilesState) -> Result<(), String> {
+    let path = session_path().ok_or("No data directory")?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let json = serde_json::to_string(state).map_err(|e| e.to_string())?;
+    write_file(&path, &json).map_err(|e| e.to_string())
+}
+
+// None when there is no session yet
+pub fn load_session() -> Result<Option<FilesState>, String> {
+    let Some(path) = session_path().filter(|path| path.exists()) else {
+        return Ok(None);
+    };
+    let json = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    serde_json::from_str(&json)
+        .map(Some)
+        .map_err(|e| format!("Failed to deserialize {}: {}", path.display(), e))
+}
+
+// the sources and outputs of the saved session, None when there is no session yet
+pub fn session_files() -> Option<HashSet<PathBuf>> {
+    let state = load_session().ok()??;
+    let lines = state.lines.iter().map(PathBuf::from);
+    let outputs = state.outputs.values().cloned();
+    let targets = state.replaced.values().map(|record| record.target.clone());
+    Some(lines.chain(outputs).chain(targets).collect())
+}
+
+// the queue and the ffmpeg processes are gone after a restart
+// queued lines go back to waiting, a running line is done when its output is complete,
+// otherwise it is interrupted: the readable length of its partial output is measured, so the
+// rest can be encoded and joined with it (resume), or the line is encoded again (restart)
+// an mp4 or mov cut off by a crash has no index (moov) and can not be read, it is restarted
+// returns the number of interrupted lines
+pub fn recover_lines(state: &mut FilesState) -> usize {
+    let mut interrupted = 0;
+    for index in 0..state.status.len() {
+        match state.status[index] {
+            TaskStatus::Queued => state.status[index] = TaskStatus::Waiting,
+            TaskStatus::Running => {
+                let source_secs = state
+                    .media
+                    .get(&index)
+                    .map_or(0.0, |info| state.output_secs(index, info.duration));
+                let output = state.outputs.get(&index);
+                // an unknown duration can not prove that the output is complete
+                let complete = source_secs > 0.0
+                    && output.is_some_and(|output| verify_output(output, source_secs).is_ok());
+                if complete {
+                    state.status[index] = TaskStatus::Done;
+                    state.partials.remove(&index);
+                    continue;
+                }
+                let size = output
+                    .and_then(|output| std::fs::metadata(output).ok())
+                    .map_or(0, |meta| meta.len());
+                let secs = state
+                    .partials
+                    .get(&index)
+                    .filter(|partial| partial.path.exists())
+                    .and_then(|partial| probe_media(&partial.path).ok())
+                    .map_or(0.0, |info| info.duration)
+                    .min(source_secs);
+                state.status[index] = TaskStatus::Interrupted;
+                let error = match state.partials.get_mut(&index) {
+                    Some(partial) if secs > 0.0 => {
+                        partial.secs = secs;
+                        format!(
+                            "interrupted after {:.0} of {:.0} seconds, resume encodes the rest",
+                            secs, source_secs
+                        )
+                    }
+                    _ => {
+                        state.partials.remove(&index);
+                        format!(
+                            "interrupted, the partial output ({} bytes) is encoded again on restart",
+                            size
+                        )
+                    }
+                };
+                state.errors.insert(index, error);
+                interrupted += 1;
+            }
+            _ => {}
+        }
+    }
+    interrupted
+}
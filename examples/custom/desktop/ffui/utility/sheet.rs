@@ -0,0 +1,233 @@
+use bevy::log::warn;
+use image::{DynamicImage, Rgba, RgbaImage, imageops};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::PathBuf;
+use std::time::UNIX_EPOCH;
+use tokio::process::Command;
+
+use super::ffmpeg::{frame_ffmpeg_command, scene_ffmpeg_command};
+use super::platform::data_dir;
+use crate::TOKIO_RT;
+use crate::define::{SheetConfig, SheetMode};
+
+// the contact sheets are cached in the data directory
+pub const SHEET_CACHE_DIR: &str = "sheets";
+
+// the gap between the cells of a grid
+const GAP: u32 = 4;
+
+// put the images into a grid of `columns`, each one scaled to `cell_width`
+// the cells have the height of the tallest scaled image
+pub fn tile_images(images: &[DynamicImage], columns: u32, cell_width: u32) -> RgbaImage {
+    let columns = columns.clamp(1, images.len().max(1) as u32);
+    let cells: Vec<RgbaImage> = images
+        .iter()
+        .map(|image| {
+            let height = (image.height() as u64 * cell_width as u64 / image.width().max(1) as u64)
+                .max(1) as u32;
+            imageops::thumbnail(image, cell_width, height)
+        })
+        .collect();
+    let cell_height = cells.iter().map(|cell| cell.height()).max().unwrap_or(1);
+    let rows = (cells.len() as u32).div_ceil(columns).max(1);
+    let mut sheet = RgbaImage::from_pixel(
+        columns * (cell_width + GAP) + GAP,
+        rows * (cell_height + GAP) + GAP,
+        Rgba([16, 16, 16, 255]),
+    );
+    for (i, cell) in cells.iter().enumerate() {
+        let (column, row) = (i as u32 % columns, i as u32 / columns);
+        let x = GAP + column * (cell_width + GAP);
+        let y = GAP + row * (cell_height + GAP) + (cell_height - cell.height()) / 2;
+        imageops::overlay(&mut sheet, cell, x as i64, y as i64);
+    }
+    sheet
+}
+
+// the rectangles (x, y, width, height) of the first `count` cells of a grid made by tile_images
+pub fn cell_rects(sheet: &RgbaImage, columns: u32, count: usize) -> Vec<(u32, u32, u32, u32)> {
+    let columns = columns.clamp(1, count.max(1) as u32);
+    let rows = (count as u32).div_ceil(columns).max(1);
+    let cell_width = (sheet.width().saturating_sub(GAP) / columns).saturating_sub(GAP);
+    let cell_height = (sheet.height().saturating_sub(GAP) / rows).saturating_sub(GAP);
+    (0..count as u32)
+        .map(|i| {
+            let (column, row) = (i % columns, i / columns);
+            (
+                GAP + column * (cell_width + GAP),
+                GAP + row * (cell_height + GAP),
+                cell_width,
+                cell_height,
+            )
+        })
+        .collect()
+}
+
+// the contact sheet of a file, read from the cache when the file did not change
+pub fn contact_sheet(path: &str, duration: f64, config: &SheetConfig) -> Result<RgbaImage, String> {
+    let cache = cache_path(path, config);
+    if let Some(sheet) = cache.as_ref().and_then(|cache| image::open(cache).ok()) {
+        return Ok(sheet.to_rgba8());
+    }
+    let frames = match config.mode {
+        SheetMode::Even => even_frames(path, duration, config.frames, config.height)?,
+        SheetMode::Scene => {
+            let mut frames = scene_frames(path, config)?;
+            // a clip with few scene changes is filled up with evenly spaced frames
+            if frames.len() < config.frames {
+                let even =
+                    even_frames(path, duration, config.frames - frames.len(), config.height)?;
+                frames.extend(even);
+            }
+            frames
+        }
+    };
+    let cell_width = frames.first().map_or(1, |frame| frame.width());
+    let sheet = tile_images(&frames, config.columns, cell_width);
+    if let Some(cache) = cache {
+        let saved = cache
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .map_err(|e| e.to_string())
+            .and_then(|_| sheet.save(&cache).map_err(|e| e.to_string()));
+        if let Err(e) = saved {
+            warn!("Failed to cache {}: {}", cache.display(), e);
+        }
+    }
+    Ok(sheet)
+}
+
+// one file per source, its size and modification time and the sheet config
+fn cache_path(path: &str, config: &SheetConfig) -> Option<PathBuf> {
+    let meta = std::fs::metadata(path).ok()?;
+    let modified = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    let mut hasher = DefaultHasher::new();
+    path.hash(&mut hasher);
+    meta.len().hash(&mut hasher);
+    modified.as_nanos().hash(&mut hasher);
+    format!("{:?}", config).hash(&mut hasher);
+    Some(
+        data_dir()?
+            .join(SHEET_CACHE_DIR)
+            .join(format!("{:016x}.png", hasher.finish())),
+    )
+}
+
+// `count` frames in the middle of equal parts of the duration
+fn even_frames(
+    path: &str,
+    duration: f64,
+    count: usize,
+    height: u32,
+) -> Result<Vec<DynamicImage>, String> {
+    (0..count)
+        .map(|i| {
+            let secs = duration.max(0.0) * (i as f64 + 0.5) / count as f64;
+            let png = run_ffmpeg(frame_ffmpeg_command(path, secs, Some(height)))?;
+            image::load_from_memory(&png).map_err(|e| format!("frame at {:.1}s: {}", secs, e))
+        })
+        .collect()
+}
+
+fn scene_frames(path: &str, config: &SheetConfig) -> Result<Vec<DynamicImage>, String> {
+    let cmd = scene_ffmpeg_command(path, config.scene_threshold, config.frames, config.height);
+    split_png_stream(&run_ffmpeg(cmd)?)
+        .into_iter()
+        .map(|png| image::load_from_memory(png).map_err(|e| e.to_string()))
+        .collect()
+}
+
+// the stdout of ffmpeg, the last stderr line when it failed
+pub fn run_ffmpeg(mut cmd: Command) -> Result<Vec<u8>, String> {
+    let output = TOKIO_RT
+        .block_on(cmd.output())
+        .map_err(|e| format!("Failed to run ffmpeg: {}", e))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(stderr.lines().last().unwrap_or("ffmpeg failed").to_string());
+    }
+    Ok(output.stdout)
+}
+
+// image2pipe writes the png files one after another, each one ends with an IEND chunk
+fn split_png_stream(bytes: &[u8]) -> Vec<&[u8]> {
+    const SIGNATURE: usize = 8;
+    let mut images = vec![];
+    let mut start = 0;
+    let mut pos = SIGNATURE;
+    while pos + 12 <= bytes.len() {
+        let length =
+            u32::from_be_bytes([bytes[pos], bytes[pos + 1], bytes[pos + 2], bytes[pos + 3]]);
+        let kind = &bytes[pos + 4..pos + 8];
+        // length, type, data and crc
+        pos += 12 + length as usize;
+        if kind == b"IEND" {
+            images.push(&bytes[start..pos.min(bytes.len())]);
+            start = pos;
+            pos += SIGNATURE;
+        }
+    }
+    images
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn png(width: u32, height: u32) -> Vec<u8> {
+        let mut bytes = vec![];
+        DynamicImage::ImageRgba8(RgbaImage::new(width, height))
+            .write_to(&mut Cursor::new(&mut bytes), image::ImageFormat::Png)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn splits_the_png_stream() {
+        let (first, second) = (png(16, 9), png(32, 18));
+        let stream = [first.as_slice(), second.as_slice()].concat();
+        let images = split_png_stream(&stream);
+        assert_eq!(images, [first.as_slice(), second.as_slice()]);
+        let sizes: Vec<(u32, u32)> = images
+            .iter()
+            .map(|bytes| {
+                let image = image::load_from_memory(bytes).unwrap();
+                (image.width(), image.height())
+            })
+            .collect();
+        assert_eq!(sizes, [(16, 9), (32, 18)]);
+    }
+
+    #[test]
+    fn drops_a_cut_off_png() {
+        let first = png(16, 9);
+        let second = png(16, 9);
+        let stream = [first.as_slice(), &second[..second.len() - 20]].concat();
+        assert_eq!(split_png_stream(&stream), [first.as_slice()]);
+        assert!(split_png_stream(&[]).is_empty());
+    }
+
+    #[test]
+    fn cells_of_a_tiled_sheet() {
+        let frames = vec![DynamicImage::ImageRgba8(RgbaImage::new(320, 180)); 5];
+        let sheet = tile_images(&frames, 3, 160);
+        assert_eq!(sheet.dimensions(), (496, 192));
+        assert_eq!(
+            cell_rects(&sheet, 3, 5),
+            [
+                (4, 4, 160, 90),
+                (168, 4, 160, 90),
+                (332, 4, 160, 90),
+                (4, 98, 160, 90),
+                (168, 98, 160, 90),
+            ]
+        );
+        // fewer frames than columns make a single row
+        let sheet = tile_images(&frames[..2], 3, 160);
+        assert_eq!(
+            cell_rects(&sheet, 3, 2),
+            [(4, 4, 160, 90), (168, 4, 160, 90)]
+        );
+    }
+}
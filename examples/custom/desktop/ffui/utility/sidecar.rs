@@ -0,0 +1,162 @@
+use serde_json::{Map, Value};
+use std::path::{Path, PathBuf};
+
+use super::replace::{trash_path, write_file};
+use crate::define::{MediaInfo, ReplaceRecord, SidecarKind};
+
+// what changed about the media file
+pub struct SidecarUpdate<'a> {
+    pub stem: &'a str,
+    pub ext: &'a str,
+    pub info: &'a MediaInfo,
+}
+
+// a file that describes a media file and must follow it when it is replaced
+pub trait Sidecar {
+    // the sidecar file of the media file, if the convention has one there
+    fn path(&self, media: &Path) -> Option<PathBuf>;
+    // the new content of the sidecar
+    fn update(&self, content: &str, update: &SidecarUpdate) -> Result<String, String>;
+}
+
+impl SidecarKind {
+    pub fn sidecar(&self) -> Box<dyn Sidecar> {
+        match self {
+            SidecarKind::Eagle => Box::new(EagleMetadata),
+            SidecarKind::Nfo => Box::new(KodiNfo),
+        }
+    }
+}
+
+// Eagle keeps each item in a `<id>.info` folder, next to a `metadata.json` like
+// {"id":"...","name":"clip","size":1234,"ext":"mkv","width":1920,"height":1080,"duration":12.5,...}
+pub struct EagleMetadata;
+
+impl Sidecar for EagleMetadata {
+    fn path(&self, media: &Path) -> Option<PathBuf> {
+        Some(media.parent()?.join("metadata.json"))
+    }
+
+    fn update(&self, content: &str, update: &SidecarUpdate) -> Result<String, String> {
+        let mut root: Map<String, Value> =
+            serde_json::from_str(content).map_err(|e| e.to_string())?;
+        // the folder may hold the metadata of another item
+        if let Some(name) = root
+            .get("name")
+            .and_then(Value::as_str)
+            .filter(|name| *name != update.stem)
+        {
+            return Err(format!("metadata.json belongs to {}", name));
+        }
+        let info = update.info;
+        root.insert("ext".to_string(), Value::from(update.ext));
+        root.insert("size".to_string(), Value::from(info.size));
+        if info.duration > 0.0 {
+            root.insert("duration".to_string(), Value::from(info.duration));
+        }
+        if let Some(video) = &info.video {
+            root.insert("width".to_string(), Value::from(video.width));
+            root.insert("height".to_string(), Value::from(video.height));
+            // Eagle has no codec field, only update it when it was added by hand
+            if root.contains_key("codec") {
+                root.insert("codec".to_string(), Value::from(video.codec.as_str()));
+            }
+        }
+        // keep the layout of the original file
+        if content.trim_end().contains('\n') {
+            serde_json::to_string_pretty(&root).map_err(|e| e.to_string())
+        } else {
+            serde_json::to_string(&root).map_err(|e| e.to_string())
+        }
+    }
+}
+
+// `<stem>.nfo` with a <fileinfo><streamdetails><video> block
+pub struct KodiNfo;
+
+impl Sidecar for KodiNfo {
+    fn path(&self, media: &Path) -> Option<PathBuf> {
+        Some(media.with_extension("nfo"))
+    }
+
+    fn update(&self, content: &str, update: &SidecarUpdate) -> Result<String, String> {
+        let Some(video) = &update.info.video else {
+            return Ok(content.to_string());
+        };
+        let (Some(start), Some(end)) = (content.find("<video>"), content.find("</video>")) else {
+            // no stream details, nothing to keep in sync
+            return Ok(content.to_string());
+        };
+        if end < start {
+            return Err("malformed <video> element".to_string());
+        }
+        let mut block = content[start..end].to_string();
+        block = replace_tag(&block, "codec", &video.codec);
+        block = replace_tag(&block, "width", &video.width.to_string());
+        block = replace_tag(&block, "height", &video.height.to_string());
+        if update.info.duration > 0.0 {
+            let secs = (update.info.duration.round() as u64).to_string();
+            block = replace_tag(&block, "durationinseconds", &secs);
+        }
+        Ok(format!("{}{}{}", &content[..start], block, &content[end..]))
+    }
+}
+
+// set the text of the first <tag>..</tag>, missing tags are left out
+fn replace_tag(xml: &str, tag: &str, value: &str) -> String {
+    let open = format!("<{}>", tag);
+    let close = format!("</{}>", tag);
+    let Some(start) = xml.find(&open).map(|i| i + open.len()) else {
+        return xml.to_string();
+    };
+    let Some(end) = xml[start..].find(&close).map(|i| i + start) else {
+        return xml.to_string();
+    };
+    format!("{}{}{}", &xml[..start], value, &xml[end..])
+}
+
+// rewrite the sidecars of a replaced file, each one is copied into the trash first
+// so undo can restore it, failures are returned as messages for the toast
+pub fn update_sidecars(
+    record: &mut ReplaceRecord,
+    kinds: &[SidecarKind],
+    info: &MediaInfo,
+) -> Vec<String> {
+    let mut errors = vec![];
+    let source = record.source.clone();
+    let target = record.target.clone();
+    let (Some(stem), Some(ext)) = (
+        source.file_stem().and_then(|stem| stem.to_str()),
+        target.extension().and_then(|ext| ext.to_str()),
+    ) else {
+        return errors;
+    };
+    let update = SidecarUpdate { stem, ext, info };
+    let Some(trash_dir) = record.trash.parent().map(Path::to_path_buf) else {
+        return errors;
+    };
+
+    for kind in kinds {
+        let sidecar = kind.sidecar();
+        let Some(path) = sidecar.path(&source) else {
+            continue;
+        };
+        if !path.exists() {
+            continue;
+        }
+        let result = std::fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|content| sidecar.update(&content, &update))
+            .and_then(|content| {
+                let name = path.file_name().and_then(|name| name.to_str());
+                let backup = trash_path(&trash_dir, name.unwrap_or("sidecar"));
+                std::fs::copy(&path, &backup).map_err(|e| e.to_string())?;
+                record.sidecars.push((path.clone(), backup));
+                write_file(&path, &content).map_err(|e| e.to_string())
+            });
+        if let Err(e) = result {
+            errors.push(format!("Failed to update {}: {}", path.display(), e));
+        }
+    }
+    errors
+}
@@ -1,150 +1,256 @@
 
 use crate::define::*;
-use crate::utility::{create_ffmpeg_command_libx265, snapshot_ffmpeg_command};
-use super::ffmpeg::{create_ffmpeg_command};
+use crate::utility::snapshot_ffmpeg_command;
+use super::ffmpeg::{concat_parts, create_ffmpeg_command, two_pass_commands};
+use std::path::{Path, PathBuf};
 use super::time::parse_duration;
+use super::replace::move_file;
 use tokio::io::{AsyncBufReadExt, AsyncReadExt};
+use tokio::process::Command;
+use tokio::sync::{broadcast, mpsc};
+use tokio::sync::broadcast::error::TryRecvError;
 use bevy::log::info;
 use crate::TOKIO_RT;
 
-pub fn task(index:usize,process_state: &ProcessState, path: String,soft:bool, args: Vec<ArgKeyValue>) {
+// ffmpeg writes into `staging` when it is set, the file is moved to `output` when the task is done
+// a two-pass preset runs ffmpeg twice, the progress bar shows each pass as a phase
+// a resumed task encodes the rest of the source, `resume` (the part written before) is joined
+// with it in front
+pub fn task(index:usize,process_state: &ProcessState, path: String, output: PathBuf, staging: Option<PathBuf>, resume: Option<PathBuf>, preset: Preset) {
     // preparse variations and move them into the background thread
     let tx = process_state.progress_tx.clone();
     let mut main_rx = process_state.main_tx.subscribe();
     let toast_tx = process_state.toast_tx.clone();
+    let log_tx = process_state.log_tx.clone();
 
     // start a background thread to run ffmpeg
     std::thread::spawn(move || {
-        //info!("start ffmpeg process: soft: {}", soft);
-        let _ = toast_tx.try_send(format!("start ffmpeg process: soft: {}", soft));
-        let mut cmd = if soft {
-            create_ffmpeg_command_libx265(path,&args)
-        }else{
-            create_ffmpeg_command(path,&args)
+        //info!("start ffmpeg process: preset: {}", preset.name);
+        let _ = toast_tx.try_send(format!("start ffmpeg process: preset: {}", preset.name));
+        let written = staging.clone().unwrap_or_else(|| output.clone());
+        // the pid keeps the line indexes of ffui and of a headless run apart
+        let passlog = std::env::temp_dir().join("ffui").join(format!("passlog-{}-{}", std::process::id(), index));
+        let commands: Vec<Command> = if preset.two_pass {
+            let _ = std::fs::create_dir_all(std::env::temp_dir().join("ffui"));
+            two_pass_commands(&path,&written,&preset.args,&passlog).into()
+        } else {
+            vec![create_ffmpeg_command(path,&written,&preset.args)]
         };
 
-        let mut process = ManagedProcess::new(&mut cmd).unwrap();
+        let passes = commands.len() as u32;
+        let mut end = PassEnd::Done;
+        for (pass, mut cmd) in (1..).zip(commands) {
+            if passes > 1 {
+                let _ = tx.blocking_send(ProgressInfo::pass(pass, passes, index));
+            }
+            end = run_pass(index, &mut cmd, &written, &tx, &mut main_rx, &toast_tx, &log_tx);
+            if !matches!(end, PassEnd::Done) {
+                break;
+            }
+        }
+        if preset.two_pass {
+            remove_passlog(&passlog);
+        }
+
+        let message = match end {
+            // already reported (cancel) or nobody is listening (window close)
+            PassEnd::Stopped => return,
+            PassEnd::Done => match resume.as_deref().map(|first| join_resumed(index, first, &written)) {
+                Some(Err(msg)) => Some(msg),
+                _ => match &staging {
+                    Some(staging) => move_file(staging, &output).err().map(|e| e.to_string()),
+                    None => None,
+                },
+            },
+            PassEnd::Failed(msg) => Some(msg),
+        };
+        match message {
+            None => {
+                //info!("task completed");
+                let _ = toast_tx.try_send("task completed".to_string());
+                let _ = tx.blocking_send(ProgressInfo::finished(index));
+            }
+            Some(msg) => {
+                let _ = toast_tx.try_send(format!("task {} failed: {}", index + 1, msg));
+                let _ = tx.blocking_send(ProgressInfo::failed(msg, index));
+            }
+        }
+    });
+}
+
+// how one ffmpeg run ended
+enum PassEnd {
+    Done,
+    Stopped, // cancelled by the user or the window was closed
+    Failed(String),
+}
+
+// put the part written before the interruption in front of the rest, the result replaces `rest`
+fn join_resumed(index: usize, first: &Path, rest: &Path) -> Result<(), String> {
+    let ext = rest.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    let joined = std::env::temp_dir().join("ffui").join(format!("joined-{}-{}{}", std::process::id(), index, ext));
+    if let Err(e) = concat_parts(&[first, rest], &joined) {
+        let _ = std::fs::remove_file(&joined);
+        return Err(format!("failed to join the resumed parts: {}", e));
+    }
+    move_file(&joined, rest).map_err(|e| e.to_string())?;
+    let _ = std::fs::remove_file(first);
+    Ok(())
+}
+
+// ffmpeg names the statistics `<passlog>-0.log` (and `.mbtree`, `.temp` files of x264)
+fn remove_passlog(passlog: &Path) {
+    let (Some(dir), Some(prefix)) = (passlog.parent(), passlog.file_name()) else {
+        return;
+    };
+    let prefix = format!("{}-", prefix.to_string_lossy());
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        if entry.file_name().to_string_lossy().starts_with(&prefix) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+// run one ffmpeg process and report its progress until it exits
+fn run_pass(
+    index: usize,
+    cmd: &mut Command,
+    written: &Path,
+    tx: &mpsc::Sender<ProgressInfo>,
+    main_rx: &mut broadcast::Receiver<ProcessSignal>,
+    toast_tx: &mpsc::Sender<String>,
+    log_tx: &mpsc::Sender<(usize, String)>,
+) -> PassEnd {
+        let mut process = match ManagedProcess::new(cmd) {
+            Ok(process) => process,
+            Err(e) => {
+                let msg = format!("failed to start ffmpeg: {}", e);
+                let _ = log_tx.blocking_send((index, msg.clone()));
+                return PassEnd::Failed(msg);
+            }
+        };
         let (stdout, stderr) = (process.stdout(), process.stderr());
 
         // 标准输出
         let mut stdout_lines = stdout.lines();
         let mut stderr_lines = stderr.lines();
         let mut stdoff = 0b00;
+        // the last line ffmpeg wrote to stderr, reported when the task fails
+        let mut last_error: Option<String> = None;
+        // statistics of the current progress block, sent on each `progress=` line
+        let mut stats = EncodeStats::default();
+        // ffmpeg reports `progress=end` only when the output is completely written
+        let mut ended = false;
         
         loop {
             // wait for the ffmpeg process to finish until the main thread signals
-            if let Ok(signal) = main_rx.try_recv(){
-                if matches!(signal,ProcessSignal::WindowClose) {
-                    info!("task stopped by main thread");
-                    return;
+            // the channel is shared by all tasks, so drain it and only react to our own index
+            loop {
+                match main_rx.try_recv() {
+                    Ok(ProcessSignal::WindowClose) => {
+                        info!("task stopped by main thread");
+                        return PassEnd::Stopped;
+                    }
+                    Ok(ProcessSignal::TaskInterrupt(i)) if i == index => {
+                        let _ = process.kill();
+                        // remove the partial output file
+                        let _ = std::fs::remove_file(written);
+                        let _ = toast_tx.try_send(format!("task {} cancelled", index + 1));
+                        let _ = tx.blocking_send(ProgressInfo::cancelled(index));
+                        return PassEnd::Stopped;
+                    }
+                    Ok(_) | Err(TryRecvError::Lagged(_)) => continue,
+                    Err(_) => break,
                 }
             }
             
-            // return if stderr and stdout are both EOF
+            // check the exit status if stderr and stdout are both EOF
             if stdoff == 0b11 {
-                //info!("task completed");
-                let _ = toast_tx.try_send("task completed".to_string());
-                return;
+                let status = TOKIO_RT.block_on(async { process.child.wait().await });
+                return match status {
+                    Ok(status) if status.success() && ended => PassEnd::Done,
+                    Ok(status) if status.success() => PassEnd::Failed("ffmpeg exited without progress=end".to_string()),
+                    Ok(status) => PassEnd::Failed(last_error.take().unwrap_or_else(|| format!("ffmpeg exited with {}", status))),
+                    Err(e) => PassEnd::Failed(format!("failed to wait for ffmpeg: {}", e)),
+                };
             }
 
             TOKIO_RT.block_on(async{
                 // read stdout and stderr lines (non-blocking)
                 tokio::select! {
-                    line = stdout_lines.next_line()=>{
-                        match line.unwrap() {
-                            Some(lin)=>{
-                                if lin.contains("out_time=") {
-                                    let Some(duration) = parse_duration(
-                                    lin.trim().trim_start_matches("out_time=").trim(),
-                                    ) else {
-                                        //info!("stdout: parse failed: {}", lin);
-                                        return;
-                                    };
-                                    tx.send(ProgressInfo::current(duration.as_secs(), index)).await.unwrap();
-                                } 
+                    line = stdout_lines.next_line(), if stdoff & 0b01 == 0 =>{
+                        match line {
+                            Ok(Some(lin))=>{
+                                let Some((key, value)) = lin.trim().split_once('=') else {
+                                    return;
+                                };
+                                match key {
+                                    "out_time" => {
+                                        let Some(duration) = parse_duration(value.trim()) else {
+                                            //info!("stdout: parse failed: {}", lin);
+                                            return;
+                                        };
+                                        let _ = tx.send(ProgressInfo::current(duration.as_secs(), index)).await;
+                                    }
+                                    // the last key of each progress block
+                                    "progress" => {
+                                        ended = value.trim() == "end";
+                                        let _ = tx.send(ProgressInfo::stats(stats.clone(), index)).await;
+                                    }
+                                    _ => stats.apply(key, value),
+                                }
                             }
-                            None=>{
+                            _=>{
                                 // complete(EOF)
                                 stdoff |= 0b01;
                                 return;
                             }
                         }
                     }
-                    _line = stderr_lines.next_line()=>{
-                        // do nothing
+                    line = stderr_lines.next_line(), if stdoff & 0b10 == 0 =>{
+                        match line {
+                            Ok(Some(lin))=>{
+                                // the statistics line is refreshed with '\r', keep the latest one
+                                let lin = lin.rsplit('\r').next().unwrap_or_default().trim().to_string();
+                                if lin.is_empty() {
+                                    return;
+                                }
+                                last_error = Some(lin.clone());
+                                let _ = log_tx.send((index, lin)).await;
+                            }
+                            _=>{
+                                // complete(EOF)
+                                stdoff |= 0b10;
+                                return;
+                            }
+                        }
                     }
                 }
             });
         }
-    });
 }
 
-pub fn replace(index:usize,path:String,data: &mut PathDatas) {
-    let filename = std::path::Path::new(&path)
-        .file_stem()
-        .and_then(|name| name.to_str())
-        .map(|name_str| format!("{}.mp4", name_str))
-        .expect("Invalid file path");
-    let dir =  std::path::Path::new(&path)
-        .parent()
-        .and_then(|name| name.to_str())
-        .expect("Failed to get parent directory");
-
-    let ext = std::path::Path::new(&path)
-        .extension()
-        .and_then(|ext| ext.to_str())
-        .unwrap_or("unknown");
-
-    let  target = format!("{}/{}", dir, filename);
-    // info!("local: {}", filename);
-    // info!("remote: {}", path);
-    // info!("target: {}", target);
-
-    // To avoid "Cross-device link" error, use copy and remove instead of rename
-    // remove remote file
-    std::fs::remove_file(&path)
-        .expect("Failed to remove file");
-    // copy local file to remote
-    std::fs::copy(&filename, &target)
-        .expect("Failed to copy file");
-    // remove local file
-    std::fs::remove_file(&filename)
-        .expect("Failed to remove file");
-
-    // replace metadata.json file 
-    let metadata_path = format!("{}/metadata.json", dir);
-    //info!("metadata path: {}", metadata_path);
-    let content = std::fs::read_to_string(&metadata_path).unwrap();
-    let from_str = format!("\"ext\":\"{}\"",ext);
-    let to_str = format!("\"ext\":\"mp4\"");
-    //info!("replace {} with {}", from_str, to_str);
-    let new_content = content.replace(&from_str, &to_str);
-    std::fs::write(metadata_path, new_content).unwrap();
-
-    data.state.status[index] = TaskStatus::Replaced;
-}
-
-pub fn snapshot(path:String,source:bool,total_secs:u64,args:Vec<ArgKeyValue>)->Vec<u8>{
+// the png bytes of one frame
+pub fn snapshot(path:String,total_secs:u64,args:Vec<ArgKeyValue>)->Result<Vec<u8>,String>{
 
         // info!("snapshot ffmpeg process");
-        let mut cmd = snapshot_ffmpeg_command(path,source,total_secs,&args);
+        let mut cmd = snapshot_ffmpeg_command(path,total_secs,&args);
 
-        let mut process = ManagedProcess::new(&mut cmd).unwrap();
+        let mut process = ManagedProcess::new(&mut cmd).map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
         let buf = TOKIO_RT.block_on(async move {
             let mut png_bytes = Vec::new();
-            process.child.stdout.as_mut().unwrap().read_to_end(&mut png_bytes).await.unwrap();
-            println!("snapshot bytes: {}", png_bytes.len());
-            process.child.wait().await.unwrap();
-            png_bytes
-        });
+            if let Some(stdout) = process.child.stdout.as_mut() {
+                stdout.read_to_end(&mut png_bytes).await.map_err(|e| e.to_string())?;
+            }
+            process.child.wait().await.map_err(|e| e.to_string())?;
+            Ok::<_, String>(png_bytes)
+        })?;
         //info!("snapshot completed");
-        buf
+        if buf.is_empty() {
+            return Err("ffmpeg returned no frame".to_string());
+        }
+        Ok(buf)
 }
-
-pub fn open_dir(path:String){
-    let dir =  std::path::Path::new(&path)
-        .parent()
-        .and_then(|name| name.to_str())
-        .expect("Failed to get parent directory");
-    std::process::Command::new("open").arg(dir).spawn().unwrap();
-}
\ No newline at end of file
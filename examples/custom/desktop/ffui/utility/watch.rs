@@ -0,0 +1,116 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use super::replace::TRASH_DIR;
+use crate::define::{WatchFolder, WatchState};
+
+// how often the folders are scanned
+const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+// a file found in a watch folder, with the preset of `auto_queue`
+#[derive(Debug)]
+pub struct WatchedFile {
+    pub path: String,
+    pub auto_queue: Option<String>,
+}
+
+// start the thread that scans the watch folders
+// `known` are the files of the saved session, the other files of the folders were added
+// while ffui was closed and are imported, without a session all the files count as known
+pub fn start_watch(folders: Vec<WatchFolder>, known: Option<HashSet<PathBuf>>) -> WatchState {
+    let (folder_tx, folder_rx) = mpsc::unbounded_channel::<WatchFolder>();
+    let (file_tx, file_rx) = mpsc::channel::<WatchedFile>(100);
+    std::thread::spawn(move || watch_folders(folders, known, folder_rx, file_tx));
+    WatchState { folder_tx, file_rx }
+}
+
+// files already in a folder when it is registered are not imported
+// a new file is sent once its size stayed the same for `stable_secs`
+fn watch_folders(
+    mut folders: Vec<WatchFolder>,
+    known: Option<HashSet<PathBuf>>,
+    mut folder_rx: mpsc::UnboundedReceiver<WatchFolder>,
+    file_tx: mpsc::Sender<WatchedFile>,
+) {
+    let mut known = known.unwrap_or_else(|| folders.iter().flat_map(scan).collect());
+    let mut pending: HashMap<PathBuf, (u64, Instant)> = HashMap::new();
+    loop {
+        while let Ok(folder) = folder_rx.try_recv() {
+            known.extend(scan(&folder));
+            // a folder sent again comes with new settings
+            folders.retain(|old| old.path != folder.path);
+            folders.push(folder);
+        }
+        let mut found = HashSet::new();
+        for folder in &folders {
+            for path in scan(folder) {
+                found.insert(path.clone());
+                if known.contains(&path) {
+                    continue;
+                }
+                let Ok(size) = std::fs::metadata(&path).map(|meta| meta.len()) else {
+                    continue;
+                };
+                let (last_size, since) = pending
+                    .entry(path.clone())
+                    .or_insert((size, Instant::now()));
+                if *last_size != size {
+                    // still being written
+                    *last_size = size;
+                    *since = Instant::now();
+                    continue;
+                }
+                if size == 0 || since.elapsed() < Duration::from_secs(folder.stable_secs) {
+                    continue;
+                }
+                let file = WatchedFile {
+                    path: path.to_string_lossy().to_string(),
+                    auto_queue: folder.auto_queue.clone(),
+                };
+                if file_tx.blocking_send(file).is_err() {
+                    return; // the app is closed
+                }
+                pending.remove(&path);
+                known.insert(path);
+            }
+        }
+        // deleted or renamed files
+        pending.retain(|path, _| found.contains(path));
+        std::thread::sleep(POLL_INTERVAL);
+    }
+}
+
+// the files of a folder that match its patterns
+fn scan(folder: &WatchFolder) -> Vec<PathBuf> {
+    let mut files = vec![];
+    scan_dir(Path::new(&folder.path), folder, &mut files);
+    files
+}
+
+fn scan_dir(dir: &Path, folder: &WatchFolder, files: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        // hidden files, the trash and the temporary files of ffui
+        if name.starts_with('.') || name.ends_with(".ffui_tmp") || name == TRASH_DIR {
+            continue;
+        }
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        if file_type.is_dir() {
+            if folder.recursive {
+                scan_dir(&path, folder, files);
+            }
+        } else if folder.matches(name) {
+            files.push(path);
+        }
+    }
+}